@@ -0,0 +1,91 @@
+//! A-Buffer
+
+use framebuffer::FrameBuffer;
+
+/// Marks the end of a fragment list.
+const END: u32 = u32::max_value();
+
+/// A transparent fragment waiting to be composited.
+#[derive(Debug, Copy, Clone)]
+pub struct Fragment {
+    pub color: [u8; 3],
+    pub alpha: f32,
+    /// Linear view depth used to sort the fragments of a pixel
+    pub depth: f32,
+    /// Bit `n` is set if the fragment covers sample `n` of the pixel
+    pub coverage: u8,
+}
+
+/// Fragment list node.
+struct Node {
+    fragment: Fragment,
+    next: u32,
+}
+
+/// Per-pixel linked lists of transparent fragments for order-independent transparency.
+/// Fragments are stored in any order and sorted per pixel when composited.
+pub struct ABuffer {
+    heads: Vec<u32>,
+    nodes: Vec<Node>,
+    width: usize,
+}
+
+impl ABuffer {
+    /// Returns a new empty `ABuffer` with `width` and `height`.
+    pub fn new(width: usize, height: usize) -> ABuffer {
+        ABuffer {
+            heads: vec![END; width * height],
+            nodes: Vec::new(),
+            width,
+        }
+    }
+
+    /// Resizes and clears this `ABuffer`.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = ABuffer::new(width, height);
+    }
+
+    /// Removes every fragment.
+    pub fn clear(&mut self) {
+        for head in &mut self.heads {
+            *head = END;
+        }
+        self.nodes.clear();
+    }
+
+    /// Adds `fragment` to the list at `x`, `y`.
+    pub fn insert(&mut self, x: usize, y: usize, fragment: Fragment) {
+        let i = x + self.width * y;
+        self.nodes.push(Node { fragment, next: self.heads[i] });
+        self.heads[i] = (self.nodes.len() - 1) as u32;
+    }
+
+    /// Blends the fragments of every pixel back to front over the samples of `framebuffer`.
+    pub fn composite(&self, framebuffer: &mut FrameBuffer) {
+        let mut fragments = Vec::new();
+
+        for (i, &head) in self.heads.iter().enumerate() {
+            if head == END {
+                continue;
+            }
+
+            fragments.clear();
+            let mut node = head;
+            while node != END {
+                fragments.push(self.nodes[node as usize].fragment);
+                node = self.nodes[node as usize].next;
+            }
+            fragments.sort_by(|a, b| b.depth.partial_cmp(&a.depth).unwrap_or(::std::cmp::Ordering::Equal));
+
+            let (x, y) = (i % self.width, i / self.width);
+            for sample in 0..framebuffer.sample_count() {
+                let mut color = framebuffer.get_sample(x, y, sample);
+                for fragment in fragments.iter().filter(|f| f.coverage & (1 << sample) != 0) {
+                    let mix = |front: u8, back: u8| (f32::from(front) * fragment.alpha + f32::from(back) * (1.0 - fragment.alpha)) as u8;
+                    color = [mix(fragment.color[0], color[0]), mix(fragment.color[1], color[1]), mix(fragment.color[2], color[2])];
+                }
+                framebuffer.set_sample(color, x, y, sample);
+            }
+        }
+    }
+}
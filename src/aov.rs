@@ -0,0 +1,237 @@
+//! Arbitrary Output Variables
+
+extern crate tdmath;
+
+use std::f32;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use image;
+use self::tdmath::Vector3;
+
+/// An extra output pass written alongside the color image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Aov {
+    /// Linear view space depth of the visible surface
+    Depth,
+    /// World space normals
+    WorldNormal,
+    /// View space normals
+    ViewNormal,
+    /// Interpolated texture coordinates
+    Uv,
+    /// Scene node of the visible surface
+    ObjectId,
+    /// Triangle index of the visible surface within its model
+    TriangleId,
+}
+
+impl Aov {
+    /// Returns the `Aov` with the config name `name`.
+    pub fn from_name(name: &str) -> Option<Aov> {
+        match name {
+            "depth" => Some(Aov::Depth),
+            "world_normal" => Some(Aov::WorldNormal),
+            "view_normal" => Some(Aov::ViewNormal),
+            "uv" => Some(Aov::Uv),
+            "object_id" => Some(Aov::ObjectId),
+            "triangle_id" => Some(Aov::TriangleId),
+            _ => None,
+        }
+    }
+
+    /// Returns the config name of this pass, also used as the output file suffix.
+    pub fn name(&self) -> &str {
+        match *self {
+            Aov::Depth => "depth",
+            Aov::WorldNormal => "world_normal",
+            Aov::ViewNormal => "view_normal",
+            Aov::Uv => "uv",
+            Aov::ObjectId => "object_id",
+            Aov::TriangleId => "triangle_id",
+        }
+    }
+}
+
+/// File format of the depth pass.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DepthFormat {
+    /// 16-bit grayscale PNG with the near plane at 0 and the far plane at 65535
+    Png,
+    /// Portable float map holding the depth in scene units
+    Pfm,
+}
+
+/// Per-pixel values of the visible surface recorded while rendering.
+pub struct AovBuffers {
+    depth: Vec<f32>,
+    world_normals: Vec<Vector3>,
+    view_normals: Vec<Vector3>,
+    uvs: Vec<(f32, f32)>,
+    object_ids: Vec<u32>,
+    triangle_ids: Vec<u32>,
+    width: usize,
+    height: usize,
+}
+
+impl AovBuffers {
+    /// Returns new empty `AovBuffers` with `width` and `height`.
+    pub fn new(width: usize, height: usize) -> AovBuffers {
+        let size = width * height;
+        AovBuffers {
+            depth: vec![f32::INFINITY; size],
+            world_normals: vec![Vector3::new(0.0, 0.0, 0.0); size],
+            view_normals: vec![Vector3::new(0.0, 0.0, 0.0); size],
+            uvs: vec![(0.0, 0.0); size],
+            object_ids: vec![0; size],
+            triangle_ids: vec![0; size],
+            width,
+            height,
+        }
+    }
+
+    /// Resizes and clears these buffers.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = AovBuffers::new(width, height);
+    }
+
+    /// Clears every pixel to the background.
+    pub fn clear(&mut self) {
+        for i in 0..self.depth.len() {
+            self.depth[i] = f32::INFINITY;
+            self.world_normals[i] = Vector3::new(0.0, 0.0, 0.0);
+            self.view_normals[i] = Vector3::new(0.0, 0.0, 0.0);
+            self.uvs[i] = (0.0, 0.0);
+            self.object_ids[i] = 0;
+            self.triangle_ids[i] = 0;
+        }
+    }
+
+    /// Records the surface visible at `x`, `y`.
+    /// Object and triangle ids are stored one higher so that 0 marks the background.
+    pub fn set(&mut self, x: usize, y: usize, fragment: AovFragment) {
+        let i = x + self.width * y;
+        self.depth[i] = fragment.depth;
+        self.world_normals[i] = fragment.world_normal;
+        self.view_normals[i] = fragment.view_normal;
+        self.uvs[i] = fragment.uv;
+        self.object_ids[i] = fragment.object_id + 1;
+        self.triangle_ids[i] = fragment.triangle_id + 1;
+    }
+
+    /// Returns the linear depth at `x`, `y`, infinite for the background.
+    pub fn depth(&self, x: usize, y: usize) -> f32 {
+        self.depth[x + self.width * y]
+    }
+
+    /// Returns the view space normal at `x`, `y`.
+    pub fn view_normal(&self, x: usize, y: usize) -> Vector3 {
+        self.view_normals[x + self.width * y]
+    }
+
+    /// Returns the object id at `x`, `y`, 0 for the background.
+    pub fn object_id(&self, x: usize, y: usize) -> u32 {
+        self.object_ids[x + self.width * y]
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Writes `passes` next to the color image at `color_path`, e.g. `render_depth.png`.
+    /// Depth is mapped between the `near` and `far` planes when written as a PNG.
+    pub fn write(&self, color_path: &Path, passes: &[Aov], depth_format: DepthFormat, near: f32, far: f32) {
+        for pass in passes {
+            match *pass {
+                Aov::Depth if depth_format == DepthFormat::Pfm => {
+                    let depth = self.depth.iter().map(|&d| d.min(far)).collect::<Vec<_>>();
+                    self.write_pfm(&pass_path(color_path, *pass, "pfm"), &depth);
+                },
+                Aov::Depth => {
+                    let depth = self.depth.iter()
+                        .map(|&d| ((d - near) / (far - near)).max(0.0).min(1.0))
+                        .map(|d| (d * 65535.0) as u16)
+                        .collect::<Vec<_>>();
+                    self.write_png16(&pass_path(color_path, *pass, "png"), &depth, image::Gray(16));
+                },
+                Aov::WorldNormal | Aov::ViewNormal => {
+                    let normals = if *pass == Aov::WorldNormal { &self.world_normals } else { &self.view_normals };
+                    let values = normals.iter()
+                        .flat_map(|n| vec![n.x, n.y, n.z])
+                        .map(|v| ((v * 0.5 + 0.5).max(0.0).min(1.0) * 65535.0) as u16)
+                        .collect::<Vec<_>>();
+                    self.write_png16(&pass_path(color_path, *pass, "png"), &values, image::RGB(16));
+                },
+                Aov::Uv => {
+                    let values = self.uvs.iter()
+                        .flat_map(|&(u, v)| vec![u, v, 0.0])
+                        .map(|v| (v.max(0.0).min(1.0) * 65535.0) as u16)
+                        .collect::<Vec<_>>();
+                    self.write_png16(&pass_path(color_path, *pass, "png"), &values, image::RGB(16));
+                },
+                Aov::ObjectId | Aov::TriangleId => {
+                    let ids = if *pass == Aov::ObjectId { &self.object_ids } else { &self.triangle_ids };
+                    self.write_ids(&pass_path(color_path, *pass, "png"), ids);
+                },
+            }
+        }
+    }
+
+    /// Writes 16-bit `values` as a PNG.
+    fn write_png16(&self, path: &Path, values: &[u16], color: image::ColorType) {
+        let bytes = values.iter()
+            .flat_map(|v| vec![(v >> 8) as u8, *v as u8])
+            .collect::<Vec<_>>();
+
+        image::save_buffer(path, &bytes, self.width as u32, self.height as u32, color)
+            .unwrap_or_else(|_| panic!("Unable to write image: {:?}", path));
+    }
+
+    /// Writes `ids` as a PNG with the 24-bit id split across the red, green and blue channels.
+    fn write_ids(&self, path: &Path, ids: &[u32]) {
+        let bytes = ids.iter()
+            .flat_map(|id| vec![(id >> 16) as u8, (id >> 8) as u8, *id as u8])
+            .collect::<Vec<_>>();
+
+        image::save_buffer(path, &bytes, self.width as u32, self.height as u32, image::RGB(8))
+            .unwrap_or_else(|_| panic!("Unable to write image: {:?}", path));
+    }
+
+    /// Writes `values` as a grayscale portable float map.
+    fn write_pfm(&self, path: &Path, values: &[f32]) {
+        let mut f = File::create(path).unwrap_or_else(|_| panic!("Unable to create file: {:?}", path));
+        let mut contents = format!("Pf\n{} {}\n-1.0\n", self.width, self.height).into_bytes();
+
+        // PFM rows run from the bottom of the image to the top.
+        for row in values.chunks(self.width).rev() {
+            for value in row {
+                let bits = value.to_bits();
+                contents.extend_from_slice(&[bits as u8, (bits >> 8) as u8, (bits >> 16) as u8, (bits >> 24) as u8]);
+            }
+        }
+
+        f.write_all(&contents).unwrap_or_else(|_| panic!("Error writing file: {:?}", path));
+    }
+}
+
+/// Surface values of a single fragment.
+pub struct AovFragment {
+    pub depth: f32,
+    pub world_normal: Vector3,
+    pub view_normal: Vector3,
+    pub uv: (f32, f32),
+    pub object_id: u32,
+    pub triangle_id: u32,
+}
+
+/// Returns the path of `pass` next to `color_path`.
+fn pass_path(color_path: &Path, pass: Aov, extension: &str) -> PathBuf {
+    let stem = color_path.file_stem().and_then(|s| s.to_str()).unwrap_or("render");
+    color_path.with_file_name(format!("{}_{}.{}", stem, pass.name(), extension))
+}
@@ -0,0 +1,79 @@
+//! Camera Bookmarks
+
+use scene_file::CameraDescription;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use toml;
+use toml::Value;
+use toml::value::Table;
+
+/// Numbered camera views saved to a sidecar file.
+pub struct Bookmarks {
+    path: PathBuf,
+    slots: BTreeMap<u32, CameraDescription>,
+}
+
+impl Bookmarks {
+    /// Returns the bookmarks stored at `path`.
+    /// Returns an empty set of bookmarks if the file does not exist yet.
+    pub fn load(path: &Path) -> Bookmarks {
+        let mut bookmarks = Bookmarks {
+            path: path.to_path_buf(),
+            slots: BTreeMap::new(),
+        };
+
+        let mut f = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => return bookmarks,
+        };
+        let mut file_contents = String::new();
+        f.read_to_string(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", path));
+
+        let toml = file_contents.parse::<Value>().unwrap_or_else(|_| panic!("Unable to parse bookmarks: {:?}", path));
+        if let Some(entries) = toml.get("bookmarks").and_then(|b| b.as_array()) {
+            for entry in entries {
+                if let Some(slot) = entry.get("slot").and_then(|s| s.as_integer()) {
+                    bookmarks.slots.insert(slot as u32, CameraDescription::from_toml(entry));
+                }
+            }
+        }
+
+        bookmarks
+    }
+
+    /// Returns the sidecar bookmark file path for the model or scene at `path`.
+    pub fn sidecar_path(path: &Path) -> PathBuf {
+        path.with_extension("bookmarks.toml")
+    }
+
+    /// Returns the camera saved in `slot`.
+    pub fn get(&self, slot: u32) -> Option<&CameraDescription> {
+        self.slots.get(&slot)
+    }
+
+    /// Saves `camera` in `slot` and writes all bookmarks to the sidecar file.
+    pub fn set(&mut self, slot: u32, camera: CameraDescription) {
+        self.slots.insert(slot, camera);
+        self.save();
+    }
+
+    /// Writes all bookmarks to the sidecar file.
+    fn save(&self) {
+        let entries = self.slots.iter().map(|(&slot, camera)| {
+            let mut entry = camera.to_toml();
+            if let Value::Table(ref mut table) = entry {
+                table.insert("slot".to_owned(), Value::Integer(i64::from(slot)));
+            }
+            entry
+        }).collect();
+
+        let mut root = Table::new();
+        root.insert("bookmarks".to_owned(), Value::Array(entries));
+
+        let contents = toml::to_string(&Value::Table(root)).unwrap_or_else(|_| panic!("Unable to serialize bookmarks: {:?}", self.path));
+        let mut f = File::create(&self.path).unwrap_or_else(|_| panic!("Unable to create file: {:?}", self.path));
+        f.write_all(contents.as_bytes()).unwrap_or_else(|_| panic!("Error writing file: {:?}", self.path));
+    }
+}
@@ -0,0 +1,101 @@
+//! Bounding Volumes
+
+extern crate tdmath;
+
+use self::tdmath::{Vector3, Vector4, Matrix4};
+
+/// Axis aligned bounding box
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingBox {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl BoundingBox {
+    /// Returns the smallest `BoundingBox` containing `points`.
+    /// Returns None if `points` is empty.
+    pub fn from_points<I>(points: I) -> Option<BoundingBox>
+    where I: IntoIterator<Item = Vector3>
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(BoundingBox { min: first, max: first }, |bbox, p| BoundingBox {
+            min: Vector3::new(bbox.min.x.min(p.x), bbox.min.y.min(p.y), bbox.min.z.min(p.z)),
+            max: Vector3::new(bbox.max.x.max(p.x), bbox.max.y.max(p.y), bbox.max.z.max(p.z)),
+        }))
+    }
+
+    /// Returns the center of the box.
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+}
+
+/// Bounding sphere
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingSphere {
+    pub center: Vector3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Returns a `BoundingSphere` containing `points`, centered on their bounding box.
+    /// Returns None if `points` is empty.
+    pub fn from_points(points: &[Vector3]) -> Option<BoundingSphere> {
+        let center = BoundingBox::from_points(points.iter().cloned())?.center();
+        let radius = points.iter()
+            .map(|&p| length(p - center))
+            .fold(0.0, f32::max);
+
+        Some(BoundingSphere { center, radius })
+    }
+
+    /// Returns the sphere containing this sphere after it is transformed by `matrix`.
+    /// Non-uniform scale is handled by using the largest axis scale.
+    pub fn transformed(&self, matrix: Matrix4) -> BoundingSphere {
+        let scale = [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)].iter()
+            .map(|&axis| length(transform_direction(matrix, axis)))
+            .fold(0.0, f32::max);
+
+        BoundingSphere {
+            center: transform_point(matrix, self.center),
+            radius: self.radius * scale,
+        }
+    }
+
+    /// Returns the smallest sphere containing both spheres.
+    pub fn union(&self, other: &BoundingSphere) -> BoundingSphere {
+        let offset = other.center - self.center;
+        let distance = length(offset);
+
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        BoundingSphere {
+            center: self.center + offset * ((radius - self.radius) / distance),
+            radius,
+        }
+    }
+}
+
+/// Returns `point` transformed by `matrix`.
+fn transform_point(matrix: Matrix4, point: Vector3) -> Vector3 {
+    let p = matrix * Vector4::new(point.x, point.y, point.z, 1.0);
+    Vector3::new(p.x / p.w, p.y / p.w, p.z / p.w)
+}
+
+/// Returns `direction` transformed by `matrix`, ignoring translation.
+fn transform_direction(matrix: Matrix4, direction: Vector3) -> Vector3 {
+    (matrix * Vector4::new(direction.x, direction.y, direction.z, 0.0)).xyz()
+}
+
+/// Returns the length of `v`.
+fn length(v: Vector3) -> f32 {
+    Vector3::dot(v, v).sqrt()
+}
@@ -2,9 +2,37 @@
 
 extern crate tdmath;
 
-use self::tdmath::{Vector3, Quaternion};
+use self::tdmath::{Vector3, Quaternion, Matrix4};
+use transform::{quaternion_identity, quaternion_from_axis_angle, quaternion_multiply, quaternion_slerp, rotate_vector};
+use std::f32::consts::{PI, FRAC_PI_2};
+
+/// Maximum pitch angle, kept just below straight up or down so the view never flips.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Minimum distance between the camera and its target.
+const MIN_DISTANCE: f32 = 0.01;
+
+/// Extra space left around framed objects.
+const FRAME_MARGIN: f32 = 1.1;
+
+/// Near and far planes set by framing, in radii of the framed sphere.
+/// They are left loose so the sphere is not clipped when the camera dollies or flies afterwards.
+const FRAME_NEAR_RADII: f32 = 0.01;
+const FRAME_FAR_RADII: f32 = 10.0;
+
+/// Perspective field of view limits in degrees.
+const MIN_FOV: f32 = 1.0;
+const MAX_FOV: f32 = 170.0;
+
+/// Orthographic scale limits.
+const MIN_ORTHO_SCALE: f32 = 0.001;
+const MAX_ORTHO_SCALE: f32 = 10000.0;
+
+/// Smallest allowed near plane distance.
+const MIN_NEAR: f32 = 0.0001;
 
 /// 3D Projections
+#[derive(Debug, Copy, Clone)]
 pub enum Projection {
     /// Orthographic projection with scale
     Orthographic(f32),
@@ -13,19 +41,334 @@ pub enum Projection {
     Perspective(f32),
 }
 
-/// 3D Camera with position, rotation and projection.
+/// Axis aligned views of the camera target
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StandardView {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Camera control modes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CameraMode {
+    /// Rotates around and looks at the target
+    Orbit,
+
+    /// Moves freely and looks in the direction set by yaw and pitch
+    Fly,
+}
+
+/// 3D Camera with position, rotation, projection and clipping planes.
+/// `fov` and `ortho_scale` keep the zoom of each projection mode when toggling between them.
+/// The camera orbits `target` at `distance`, with the orbit angles stored in `yaw` and `pitch`.
+/// In `CameraMode::Fly` the same angles control the look direction and the target follows the camera.
+#[derive(Clone)]
 pub struct Camera {
     pub position: Vector3,
     pub rotation: Quaternion,
     pub projection: Projection,
+    pub fov: f32,
+    pub ortho_scale: f32,
+    pub near: f32,
+    pub far: f32,
+    pub target: Vector3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub mode: CameraMode,
+}
+
+impl Camera {
+    /// Rotates the camera around its target by `delta_yaw` and `delta_pitch` radians.
+    /// Pitch is clamped so the camera cannot pass over the top or bottom of the target.
+    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.yaw += delta_yaw;
+        self.pitch = (self.pitch + delta_pitch).max(-MAX_PITCH).min(MAX_PITCH);
+        self.update_orbit();
+    }
+
+    /// Moves the camera and its target along the camera's right and up axes.
+    pub fn pan(&mut self, delta_right: f32, delta_up: f32) {
+        self.target = self.target + self.right() * delta_right + self.up() * delta_up;
+        self.update_orbit();
+    }
+
+    /// Moves the camera towards or away from its target by a fraction of the current distance.
+    pub fn dolly(&mut self, amount: f32) {
+        self.distance = (self.distance * (1.0 + amount)).max(MIN_DISTANCE);
+        self.update_orbit();
+    }
+
+    /// Moves the camera to `position` while keeping it pointed at the target.
+    pub fn set_position(&mut self, position: Vector3) {
+        let offset = position - self.target;
+        let distance = Vector3::dot(offset, offset).sqrt();
+        if distance < MIN_DISTANCE {
+            return;
+        }
+
+        self.distance = distance;
+        self.yaw = offset.x.atan2(offset.z);
+        self.pitch = (offset.y / distance).max(-1.0).min(1.0).asin();
+        self.update_orbit();
+    }
+
+    /// Points the camera at the sphere at `center` and moves back until the sphere fits in the view.
+    /// The near and far planes are moved to enclose the sphere with room to move around it.
+    pub fn frame(&mut self, center: Vector3, radius: f32, aspect: f32) {
+        let radius = radius.max(MIN_DISTANCE) * FRAME_MARGIN;
+        self.target = center;
+
+        match self.projection {
+            Projection::Perspective(fov) => {
+                let half_fov = fov.to_radians() * 0.5;
+                let half_fov_x = (half_fov.tan() * aspect).atan();
+                self.distance = radius / half_fov.min(half_fov_x).sin();
+            },
+            Projection::Orthographic(_) => {
+                self.set_ortho_scale(radius / aspect.min(1.0));
+                self.distance = radius * 2.0;
+            },
+        }
+
+        self.set_clip_planes(radius * FRAME_NEAR_RADII, self.distance + radius * FRAME_FAR_RADII);
+        self.update_orbit();
+    }
+
+    /// Looks at the target along a world axis with an orthographic projection.
+    /// The camera keeps its distance so the near and far planes still enclose the target.
+    pub fn set_standard_view(&mut self, view: StandardView) {
+        let (yaw, pitch) = match view {
+            StandardView::Front => (0.0, 0.0),
+            StandardView::Back => (PI, 0.0),
+            StandardView::Left => (-FRAC_PI_2, 0.0),
+            StandardView::Right => (FRAC_PI_2, 0.0),
+            StandardView::Top => (0.0, FRAC_PI_2),
+            StandardView::Bottom => (0.0, -FRAC_PI_2),
+        };
+
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.projection = Projection::Orthographic(self.ortho_scale);
+        self.update_orbit();
+    }
+
+    /// Sets the projection and remembers its zoom for when the projection is toggled.
+    pub fn set_projection(&mut self, projection: Projection) {
+        match projection {
+            Projection::Perspective(fov) => {
+                self.projection = projection;
+                self.set_fov(fov);
+            },
+            Projection::Orthographic(scale) => {
+                self.projection = projection;
+                self.set_ortho_scale(scale);
+            },
+        }
+    }
+
+    /// Switches between perspective and orthographic projection.
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Orthographic(_) => Projection::Perspective(self.fov),
+            Projection::Perspective(_) => Projection::Orthographic(self.ortho_scale),
+        }
+    }
+
+    /// Sets the perspective field of view in degrees, clamped to a valid range.
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.max(MIN_FOV).min(MAX_FOV);
+        if let Projection::Perspective(_) = self.projection {
+            self.projection = Projection::Perspective(self.fov);
+        }
+    }
+
+    /// Sets the orthographic scale, clamped to a valid range.
+    pub fn set_ortho_scale(&mut self, scale: f32) {
+        self.ortho_scale = scale.max(MIN_ORTHO_SCALE).min(MAX_ORTHO_SCALE);
+        if let Projection::Orthographic(_) = self.projection {
+            self.projection = Projection::Orthographic(self.ortho_scale);
+        }
+    }
+
+    /// Sets the near and far clipping planes.
+    /// The near plane is kept above zero and the far plane beyond the near plane.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) {
+        self.near = near.max(MIN_NEAR);
+        self.far = far.max(self.near * 1.001);
+    }
+
+    /// Zooms by changing the field of view or orthographic scale by `amount`.
+    pub fn zoom(&mut self, amount: f32) {
+        match self.projection {
+            Projection::Perspective(fov) => self.set_fov(fov + amount),
+            Projection::Orthographic(scale) => self.set_ortho_scale(scale + amount * 0.1),
+        }
+    }
+
+    /// Turns the fly camera by `delta_yaw` and `delta_pitch` radians without moving it.
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.yaw += delta_yaw;
+        self.pitch = (self.pitch + delta_pitch).max(-MAX_PITCH).min(MAX_PITCH);
+        self.update_rotation();
+        self.target = self.position + self.forward() * self.distance;
+    }
+
+    /// Moves the fly camera by `right`, `up` and `forward` units.
+    /// `right` and `forward` follow the camera's rotation and `up` is always vertical.
+    pub fn fly(&mut self, right: f32, up: f32, forward: f32) {
+        self.position = self.position + self.right() * right + Vector3::new(0.0, up, 0.0) + self.forward() * forward;
+        self.target = self.position + self.forward() * self.distance;
+    }
+
+    /// Switches between orbit and fly controls, keeping the current view.
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        self.mode = mode;
+        self.target = self.position + self.forward() * self.distance;
+    }
+
+    /// Recalculates `rotation` from the orbit angles and `position` from the rotation, target and distance.
+    pub fn update_orbit(&mut self) {
+        self.update_rotation();
+        self.position = self.target + rotate_vector(self.rotation, Vector3::new(0.0, 0.0, self.distance));
+    }
+
+    /// Recalculates `rotation` from the yaw and pitch angles.
+    fn update_rotation(&mut self) {
+        let yaw = quaternion_from_axis_angle(Vector3::new(0.0, 1.0, 0.0), self.yaw);
+        let pitch = quaternion_from_axis_angle(Vector3::new(1.0, 0.0, 0.0), -self.pitch);
+        self.rotation = quaternion_multiply(yaw, pitch);
+    }
+
+    /// Returns a perspective projection between `fov` and an orthographic `scale`.
+    /// `ortho_amount` 0 is fully perspective and 1 is fully orthographic.
+    /// The camera distance and clipping planes are adjusted so the target stays the same size.
+    fn blend_projection(&mut self, fov: f32, scale: f32, ortho_amount: f32) -> Projection {
+        if ortho_amount >= 1.0 {
+            return Projection::Orthographic(scale);
+        }
+
+        let perspective_tan = (fov.to_radians() * 0.5).tan();
+        let half_height = lerp(self.distance * perspective_tan, scale, ortho_amount);
+        let blend_tan = lerp(perspective_tan, (MIN_FOV.to_radians() * 0.5).tan(), ortho_amount);
+
+        let distance = half_height / blend_tan;
+        let shift = distance - self.distance;
+        self.distance = distance;
+        self.near = (self.near + shift).max(MIN_NEAR);
+        self.far += shift;
+
+        Projection::Perspective(2.0 * blend_tan.atan().to_degrees())
+    }
+
+    /// Returns the direction the camera is looking.
+    pub fn forward(&self) -> Vector3 {
+        rotate_vector(self.rotation, Vector3::new(0.0, 0.0, -1.0))
+    }
+
+    /// Returns the camera's right direction.
+    pub fn right(&self) -> Vector3 {
+        rotate_vector(self.rotation, Vector3::new(1.0, 0.0, 0.0))
+    }
+
+    /// Returns the camera's up direction.
+    pub fn up(&self) -> Vector3 {
+        rotate_vector(self.rotation, Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    /// Returns the view matrix for the camera's position and rotation.
+    pub fn view_matrix(&self) -> Matrix4 {
+        Matrix4::look_at(self.position, self.position + self.forward(), self.up())
+    }
 }
 
 impl Default for Camera {
     fn default() -> Self {
-        Camera {
-            position: Vector3::new(0.0, 0.0, 0.0),
-            rotation: Quaternion::new(0.0, 0.0, 0.0),
+        let mut camera = Camera {
+            position: Vector3::new(0.0, 0.0, 10.0),
+            rotation: quaternion_identity(),
             projection: Projection::Perspective(60.0),
+            fov: 60.0,
+            ortho_scale: 5.0,
+            near: 0.1,
+            far: 50.0,
+            target: Vector3::new(0.0, 0.0, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 10.0,
+            mode: CameraMode::Orbit,
+        };
+        camera.update_orbit();
+        camera
+    }
+}
+
+/// Animation from one camera state to another.
+pub struct CameraTransition {
+    from: Camera,
+    to: Camera,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl CameraTransition {
+    /// Returns a new `CameraTransition` lasting `duration` seconds.
+    pub fn new(from: Camera, to: Camera, duration: f32) -> CameraTransition {
+        CameraTransition {
+            from,
+            to,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+
+    /// Advances the transition by `delta_time` seconds.
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed += delta_time;
+    }
+
+    /// Returns true once the transition has reached the end state.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Returns the camera at the current point of the transition.
+    /// The target, distance and clipping planes are interpolated linearly and the rotation is slerped.
+    /// Switching between perspective and orthographic narrows the FOV while moving the camera back,
+    /// keeping the size of the target constant until the view is close to orthographic.
+    pub fn camera(&self) -> Camera {
+        if self.is_finished() || self.duration <= 0.0 {
+            return self.to.clone();
         }
+
+        let t = self.elapsed / self.duration;
+        let t = t * t * (3.0 - 2.0 * t);
+        let (from, to) = (&self.from, &self.to);
+
+        let mut camera = to.clone();
+        camera.target = from.target + (to.target - from.target) * t;
+        camera.rotation = quaternion_slerp(from.rotation, to.rotation, t);
+        camera.distance = lerp(from.distance, to.distance, t);
+        camera.near = lerp(from.near, to.near, t);
+        camera.far = lerp(from.far, to.far, t);
+
+        camera.projection = match (from.projection, to.projection) {
+            (Projection::Perspective(a), Projection::Perspective(b)) => Projection::Perspective(lerp(a, b, t)),
+            (Projection::Orthographic(a), Projection::Orthographic(b)) => Projection::Orthographic(lerp(a, b, t)),
+            (Projection::Perspective(fov), Projection::Orthographic(scale)) => camera.blend_projection(fov, scale, t),
+            (Projection::Orthographic(scale), Projection::Perspective(fov)) => camera.blend_projection(fov, scale, 1.0 - t),
+        };
+
+        camera.position = camera.target + rotate_vector(camera.rotation, Vector3::new(0.0, 0.0, camera.distance));
+        camera
     }
-}
\ No newline at end of file
+}
+
+/// Returns the linear interpolation between `a` and `b` at `t`.
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
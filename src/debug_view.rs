@@ -0,0 +1,94 @@
+//! Debug Views
+
+/// What the renderer draws in place of the shaded color, for diagnosing broken assets.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DebugView {
+    /// Normal lit and textured shading
+    Shaded,
+    /// World space normals mapped from -1..1 to colors
+    Normals,
+    /// Texture coordinates in the red and green channels, repeating outside 0..1
+    Uvs,
+    /// Linear view depth, white at the near plane and black at the far plane
+    Depth,
+    /// A flat random color per triangle
+    TriangleColors,
+    /// How often each pixel passed the depth test, from blue to red
+    Overdraw,
+    /// Shaded with back faces drawn instead of culled and highlighted in red
+    BackFaces,
+}
+
+impl DebugView {
+    /// Returns the `DebugView` with the config name `name`.
+    pub fn from_name(name: &str) -> Option<DebugView> {
+        match name {
+            "shaded" => Some(DebugView::Shaded),
+            "normals" => Some(DebugView::Normals),
+            "uvs" => Some(DebugView::Uvs),
+            "depth" => Some(DebugView::Depth),
+            "triangles" => Some(DebugView::TriangleColors),
+            "overdraw" => Some(DebugView::Overdraw),
+            "back_faces" => Some(DebugView::BackFaces),
+            _ => None,
+        }
+    }
+
+    /// Returns the view after this one in the viewer's cycle.
+    pub fn next(&self) -> DebugView {
+        match *self {
+            DebugView::Shaded => DebugView::Normals,
+            DebugView::Normals => DebugView::Uvs,
+            DebugView::Uvs => DebugView::Depth,
+            DebugView::Depth => DebugView::TriangleColors,
+            DebugView::TriangleColors => DebugView::Overdraw,
+            DebugView::Overdraw => DebugView::BackFaces,
+            DebugView::BackFaces => DebugView::Shaded,
+        }
+    }
+
+    /// Returns the text representation of the view.
+    pub fn name(&self) -> &str {
+        match *self {
+            DebugView::Shaded => "Shaded",
+            DebugView::Normals => "Normals",
+            DebugView::Uvs => "UVs",
+            DebugView::Depth => "Depth",
+            DebugView::TriangleColors => "Triangles",
+            DebugView::Overdraw => "Overdraw",
+            DebugView::BackFaces => "Back Faces",
+        }
+    }
+}
+
+/// Returns a random but stable color for the triangle `triangle_id` of the scene node `object_id`.
+pub fn triangle_color(object_id: u32, triangle_id: u32) -> [u8; 3] {
+    let hash = hash(hash(object_id) ^ triangle_id);
+    [(hash >> 16) as u8, (hash >> 8) as u8, hash as u8]
+}
+
+/// Returns the heat map color for a pixel that passed the depth test `count` times.
+/// Colors run from blue for a single write through green and yellow to red for `MAX_OVERDRAW` or more.
+pub fn overdraw_color(count: u32) -> [u8; 3] {
+    const MAX_OVERDRAW: u32 = 8;
+    const STOPS: [[f32; 3]; 4] = [[0.0, 0.0, 255.0], [0.0, 255.0, 0.0], [255.0, 255.0, 0.0], [255.0, 0.0, 0.0]];
+
+    let t = (count.saturating_sub(1) as f32 / (MAX_OVERDRAW - 1) as f32).min(1.0) * (STOPS.len() - 1) as f32;
+    let stop = (t as usize).min(STOPS.len() - 2);
+    let t = t - stop as f32;
+
+    let (a, b) = (STOPS[stop], STOPS[stop + 1]);
+    [
+        (a[0] + (b[0] - a[0]) * t) as u8,
+        (a[1] + (b[1] - a[1]) * t) as u8,
+        (a[2] + (b[2] - a[2]) * t) as u8,
+    ]
+}
+
+/// Integer hash spreading similar ids to unrelated values.
+fn hash(value: u32) -> u32 {
+    let mut x = value;
+    x = ((x >> 16) ^ x).wrapping_mul(0x45d9_f3b);
+    x = ((x >> 16) ^ x).wrapping_mul(0x45d9_f3b);
+    (x >> 16) ^ x
+}
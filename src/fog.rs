@@ -0,0 +1,82 @@
+//! Fog
+
+/// How the amount of fog grows with distance from the camera.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FogMode {
+    /// Rises evenly from no fog at the start distance to full fog at the end distance
+    Linear,
+    /// Thickens exponentially with distance past the start, scaled by the density
+    Exponential,
+    /// Like exponential, but stays clear for longer and then closes in more quickly
+    ExponentialSquared,
+}
+
+impl FogMode {
+    /// Returns the `FogMode` with the config name `name`.
+    pub fn from_name(name: &str) -> Option<FogMode> {
+        match name {
+            "linear" => Some(FogMode::Linear),
+            "exp" => Some(FogMode::Exponential),
+            "exp2" => Some(FogMode::ExponentialSquared),
+            _ => None,
+        }
+    }
+
+    /// Returns the text representation of the mode.
+    pub fn name(&self) -> &str {
+        match *self {
+            FogMode::Linear => "Linear",
+            FogMode::Exponential => "Exp",
+            FogMode::ExponentialSquared => "Exp2",
+        }
+    }
+}
+
+/// Fog settings. The fog takes the color of the background so distant geometry fades into it.
+pub struct Fog {
+    pub mode: FogMode,
+    /// Density of the exponential modes
+    pub density: f32,
+    /// View depth where the fog begins
+    pub start: f32,
+    /// View depth where linear fog is complete, the far plane if not set
+    pub end: Option<f32>,
+    /// World height above which the fog thins out
+    pub height: f32,
+    /// How quickly the fog thins out above `height`, 0 keeps it even at all heights
+    pub height_falloff: f32,
+}
+
+impl Fog {
+    /// Returns how much of a surface at view depth `depth` and world height `height` is hidden by fog, from 0 to 1.
+    pub fn amount(&self, depth: f32, height: f32, far: f32) -> f32 {
+        let distance = (depth - self.start).max(0.0);
+
+        let amount = match self.mode {
+            FogMode::Linear => distance / (self.end.unwrap_or(far) - self.start).max(::std::f32::EPSILON),
+            FogMode::Exponential => 1.0 - (-self.density * distance).exp(),
+            FogMode::ExponentialSquared => 1.0 - (-(self.density * distance).powi(2)).exp(),
+        };
+
+        let thinning = if self.height_falloff > 0.0 {
+            (-self.height_falloff * (height - self.height).max(0.0)).exp()
+        } else {
+            1.0
+        };
+
+        (amount * thinning).max(0.0).min(1.0)
+    }
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            mode: FogMode::Linear,
+            density: 0.05,
+            start: 0.0,
+            end: None,
+            height: 0.0,
+            height_falloff: 0.0,
+        }
+    }
+}
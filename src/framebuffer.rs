@@ -0,0 +1,131 @@
+//! Frame Buffer
+
+use image::{RgbImage, ImageBuffer};
+
+/// RGB color buffer the renderer draws into.
+/// Colors are written to `sample_count` samples per pixel and averaged into the pixels by `resolve`.
+pub struct FrameBuffer {
+    pixels: Vec<u8>,
+    samples: Vec<[u8; 3]>,
+    overdraw: Vec<u32>,
+    width: usize,
+    height: usize,
+    sample_count: usize,
+}
+
+impl FrameBuffer {
+    /// Returns a new black `FrameBuffer` with `width` and `height`.
+    pub fn new(width: usize, height: usize) -> FrameBuffer {
+        FrameBuffer {
+            pixels: vec![0; width * height * 3],
+            samples: vec![[0; 3]; width * height],
+            overdraw: vec![0; width * height],
+            width,
+            height,
+            sample_count: 1,
+        }
+    }
+
+    /// Resizes this `FrameBuffer`.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height * 3];
+        self.samples = vec![[0; 3]; width * height * self.sample_count];
+        self.overdraw = vec![0; width * height];
+    }
+
+    /// Sets the number of samples stored per pixel.
+    pub fn set_sample_count(&mut self, sample_count: usize) {
+        self.sample_count = sample_count;
+        let (width, height) = (self.width, self.height);
+        self.resize(width, height);
+    }
+
+    /// Sets every pixel and sample to `color` and resets the overdraw counts.
+    pub fn clear(&mut self, color: [u8; 3]) {
+        for pixel in self.pixels.chunks_mut(3) {
+            pixel.copy_from_slice(&color);
+        }
+        for sample in &mut self.samples {
+            *sample = color;
+        }
+        for count in &mut self.overdraw {
+            *count = 0;
+        }
+    }
+
+    /// Counts a passed depth test at `x`, `y`.
+    pub fn add_overdraw(&mut self, x: usize, y: usize) {
+        self.overdraw[x + self.width * y] += 1;
+    }
+
+    /// Returns how many times the depth test passed at `x`, `y` since the last clear.
+    pub fn overdraw(&self, x: usize, y: usize) -> u32 {
+        self.overdraw[x + self.width * y]
+    }
+
+    /// Returns the resolved color at `x`, `y`.
+    pub fn get(&self, x: usize, y: usize) -> [u8; 3] {
+        let i = (x + self.width * y) * 3;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    /// Sets every sample at `x`, `y` to `color`.
+    pub fn set(&mut self, color: [u8; 3], x: usize, y: usize) {
+        let i = (x + self.width * y) * self.sample_count;
+        for sample in &mut self.samples[i..i + self.sample_count] {
+            *sample = color;
+        }
+    }
+
+    /// Returns the color of `sample` at `x`, `y`.
+    pub fn get_sample(&self, x: usize, y: usize, sample: usize) -> [u8; 3] {
+        self.samples[(x + self.width * y) * self.sample_count + sample]
+    }
+
+    /// Sets the color of `sample` at `x`, `y`.
+    pub fn set_sample(&mut self, color: [u8; 3], x: usize, y: usize, sample: usize) {
+        self.samples[(x + self.width * y) * self.sample_count + sample] = color;
+    }
+
+    /// Averages the samples of every pixel into the resolved pixels.
+    pub fn resolve(&mut self) {
+        for (pixel, samples) in self.pixels.chunks_mut(3).zip(self.samples.chunks(self.sample_count)) {
+            for (channel, value) in pixel.iter_mut().enumerate() {
+                let sum = samples.iter().map(|s| u32::from(s[channel])).sum::<u32>();
+                *value = (sum / self.sample_count as u32) as u8;
+            }
+        }
+    }
+
+    /// Returns the number of samples stored per pixel.
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the resolved RGB bytes, row by row from the top left.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the resolved RGB bytes for post processing.
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /// Returns a copy of the resolved pixels as an image.
+    pub fn to_image(&self) -> RgbImage {
+        ImageBuffer::from_raw(self.width as u32, self.height as u32, self.pixels.clone()).unwrap()
+    }
+}
@@ -0,0 +1,28 @@
+//! Light
+
+extern crate tdmath;
+
+use self::tdmath::Vector3;
+
+/// Directional light
+#[derive(Debug, Copy, Clone)]
+pub struct Light {
+    pub direction: Vector3,
+    pub intensity: f32,
+}
+
+impl Light {
+    /// Returns a new `Light` shining in `direction`.
+    pub fn new(direction: Vector3, intensity: f32) -> Light {
+        Light {
+            direction: direction.normalized(),
+            intensity,
+        }
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Light::new(Vector3::new(0.0, 0.0, -1.0), 1.0)
+    }
+}
@@ -12,16 +12,41 @@ pub mod camera;
 pub mod settings;
 pub mod material;
 pub mod text;
+pub mod stl;
+pub mod ply;
+pub mod transform;
+pub mod scene;
+pub mod scene_file;
+pub mod light;
+pub mod mtl;
+pub mod bounds;
+pub mod bookmarks;
+pub mod framebuffer;
+pub mod turntable;
+pub mod screenshot;
+pub mod aov;
+pub mod postprocess;
+pub mod debug_view;
+pub mod abuffer;
+pub mod shading;
+pub mod toon;
+pub mod ssao;
+pub mod fog;
 
 use sdl2::pixels::Color;
 use sdl2::event::{Event, WindowEvent};
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Scancode, LCTRLMOD, RCTRLMOD};
 use sdl2::mouse::MouseButton;
+use sdl2::pixels::PixelFormatEnum;
 use std::time::{Duration, SystemTime};
+use std::path::Path;
+use std::process;
 pub use renderer::Renderer;
 use settings::Settings;
 use cmdpro::{CommandLineProcessor, ParameterType, ParameterValue};
 use text::*;
+use camera::StandardView;
+use bookmarks::Bookmarks;
 use std::collections::HashMap;
 use tdmath::Vector2i;
 
@@ -29,8 +54,23 @@ fn main() {
     let mut command_line_processor = CommandLineProcessor::new();
     command_line_processor.add_parameter("model", ParameterType::Path, vec!["--model".to_owned(), "--m".to_owned()]);
     command_line_processor.add_parameter("material", ParameterType::Path, vec!["--material".to_owned()]);
+    command_line_processor.add_parameter("scene", ParameterType::Path, vec!["--scene".to_owned()]);
     command_line_processor.add_parameter("width", ParameterType::UInteger, vec!["--width".to_owned(), "--w".to_owned()]);
     command_line_processor.add_parameter("height", ParameterType::UInteger, vec!["--height".to_owned(), "--h".to_owned()]);
+    command_line_processor.add_parameter("config", ParameterType::Path, vec!["--config".to_owned()]);
+    command_line_processor.add_parameter("near", ParameterType::Float, vec!["--near".to_owned()]);
+    command_line_processor.add_parameter("far", ParameterType::Float, vec!["--far".to_owned()]);
+    command_line_processor.add_parameter("fov", ParameterType::Float, vec!["--fov".to_owned()]);
+    command_line_processor.add_parameter("ortho-scale", ParameterType::Float, vec!["--ortho-scale".to_owned()]);
+    command_line_processor.add_parameter("transition-time", ParameterType::Float, vec!["--transition-time".to_owned()]);
+    command_line_processor.add_parameter("turntable", ParameterType::Path, vec!["--turntable".to_owned()]);
+    command_line_processor.add_parameter("frames", ParameterType::UInteger, vec!["--frames".to_owned()]);
+    command_line_processor.add_parameter("elevation", ParameterType::Float, vec!["--elevation".to_owned()]);
+    command_line_processor.add_parameter("render", ParameterType::Path, vec!["--render".to_owned()]);
+    command_line_processor.add_parameter("msaa", ParameterType::UInteger, vec!["--msaa".to_owned()]);
+    command_line_processor.add_parameter("render-scale", ParameterType::UInteger, vec!["--render-scale".to_owned()]);
+    command_line_processor.add_parameter("screenshot-scale", ParameterType::UInteger, vec!["--screenshot-scale".to_owned()]);
+    command_line_processor.add_parameter("matcap", ParameterType::Path, vec!["--matcap".to_owned()]);
     command_line_processor.set_help_text(include_str!("help.txt"));
     command_line_processor.parse_command_line();
 
@@ -40,6 +80,21 @@ fn main() {
 
     let settings = Settings::from_commandline(&command_line_processor);
 
+    if let Some(turntable_path) = settings.turntable_path() {
+        let mut renderer = create_renderer(&settings, &command_line_processor);
+        if let Err(message) = turntable::export(&mut renderer, turntable_path, settings.turntable_frames(), settings.turntable_elevation(), settings.aovs(), settings.depth_format()) {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(render_path) = settings.render_path() {
+        let mut renderer = create_renderer(&settings, &command_line_processor);
+        render_image(&mut renderer, render_path, &settings);
+        return;
+    }
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let ttf_context = sdl2::ttf::init().unwrap();
@@ -53,8 +108,12 @@ fn main() {
     let mut canvas = window.into_canvas().build().unwrap();
     let texture_creator = canvas.texture_creator();    
 
-    let mut renderer = Renderer::new(settings.width() as usize, settings.height() as usize);
-    renderer.load_models(vec!(settings.model_path()));
+    let mut renderer = create_renderer(&settings, &command_line_processor);
+    let mut frame_texture = texture_creator.create_texture_streaming(PixelFormatEnum::RGB24, settings.width(), settings.height()).unwrap();
+
+    let mut bookmarks = settings.scene_path()
+        .or_else(|| settings.model_path())
+        .map(|path| Bookmarks::load(&Bookmarks::sidecar_path(path)));
 
     let font = ttf_context.load_font("fonts/UbuntuMono-R.ttf", 16).unwrap();
     let mut text_map = HashMap::new();
@@ -71,25 +130,90 @@ fn main() {
     smooth_text.set_anchor(Anchor::BottomLeft);
     text_map.insert(TextID::SmoothShading, smooth_text);
 
-    if let ParameterValue::Path(material_path) = command_line_processor.get_parameter_value("material") {
-       renderer.load_material(material_path);
-    }
+    let mut camera_text = Text::default();
+    camera_text.set_text(&font, &texture_creator, &format!("(C)amera: {}", renderer.camera_mode_str()), Color::RGBA(255, 0, 0, 255));
+    camera_text.set_offset(Vector2i::new(0, -75));
+    camera_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::CameraMode, camera_text);
+
+    let mut selection_text = Text::default();
+    selection_text.set_text(&font, &texture_creator, &format!("(Tab) Selected: {}", renderer.selected_str()), Color::RGBA(255, 0, 0, 255));
+    selection_text.set_offset(Vector2i::new(0, -100));
+    selection_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Selection, selection_text);
+
+    let mut zoom_text = Text::default();
+    zoom_text.set_text(&font, &texture_creator, &format!("(Z)oom: {}", renderer.zoom_mode_str()), Color::RGBA(255, 0, 0, 255));
+    zoom_text.set_offset(Vector2i::new(0, -125));
+    zoom_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::ZoomMode, zoom_text);
+
+    let mut msaa_text = Text::default();
+    msaa_text.set_text(&font, &texture_creator, &format!("(M)SAA: {}", renderer.msaa_str()), Color::RGBA(255, 0, 0, 255));
+    msaa_text.set_offset(Vector2i::new(0, -150));
+    msaa_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Msaa, msaa_text);
+
+    let mut supersampling_text = Text::default();
+    supersampling_text.set_text(&font, &texture_creator, &format!("(X) Supersampling: {}", renderer.supersampling_str()), Color::RGBA(255, 0, 0, 255));
+    supersampling_text.set_offset(Vector2i::new(0, -175));
+    supersampling_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Supersampling, supersampling_text);
+
+    let mut fxaa_text = Text::default();
+    fxaa_text.set_text(&font, &texture_creator, &format!("(V) FXAA: {}", renderer.fxaa_str()), Color::RGBA(255, 0, 0, 255));
+    fxaa_text.set_offset(Vector2i::new(0, -200));
+    fxaa_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Fxaa, fxaa_text);
 
+    let mut debug_view_text = Text::default();
+    debug_view_text.set_text(&font, &texture_creator, &format!("(N) View: {}", renderer.debug_view_str()), Color::RGBA(255, 0, 0, 255));
+    debug_view_text.set_offset(Vector2i::new(0, -225));
+    debug_view_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::DebugView, debug_view_text);
+
+    let mut transparency_text = Text::default();
+    transparency_text.set_text(&font, &texture_creator, &format!("(O) Transparency: {}", renderer.transparency_str()), Color::RGBA(255, 0, 0, 255));
+    transparency_text.set_offset(Vector2i::new(0, -250));
+    transparency_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Transparency, transparency_text);
+
+    let mut shading_text = Text::default();
+    shading_text.set_text(&font, &texture_creator, &format!("(T) Shading: {}", renderer.shading_str()), Color::RGBA(255, 0, 0, 255));
+    shading_text.set_offset(Vector2i::new(0, -275));
+    shading_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Shading, shading_text);
+
+    let mut ssao_text = Text::default();
+    ssao_text.set_text(&font, &texture_creator, &format!("(G) SSAO: {}", renderer.ssao_str()), Color::RGBA(255, 0, 0, 255));
+    ssao_text.set_offset(Vector2i::new(0, -300));
+    ssao_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Ssao, ssao_text);
+
+    let mut fog_text = Text::default();
+    fog_text.set_text(&font, &texture_creator, &format!("(B) Fog: {}", renderer.fog_str()), Color::RGBA(255, 0, 0, 255));
+    fog_text.set_offset(Vector2i::new(0, -325));
+    fog_text.set_anchor(Anchor::BottomLeft);
+    text_map.insert(TextID::Fog, fog_text);
     canvas.set_draw_color(Color::RGB(0, 0, 0));
     canvas.clear();
     canvas.present();
 
-    let mut mouse_down = false;
-    // Set camera position
-    renderer.orbit(0.0, 0.0);
+    let mut orbit_down = false;
+    let mut pan_down = false;
+    let mut dolly_down = false;
     let target_frame_rate = 1_000_000_000u32 / 60;
+    let fly_speed = 5.0;
+    let mut last_frame_time = SystemTime::now();
 
     let mut event_pump = sdl_context.event_pump().unwrap();
     'running: loop {
         let frame_start_time = SystemTime::now();
-
-        canvas.set_draw_color(Color::RGB(65, 65, 65));
-        canvas.clear();
+        let delta_time = match frame_start_time.duration_since(last_frame_time) {
+            Ok(t) => t.as_secs() as f32 + t.subsec_nanos() as f32 / 1_000_000_000.0,
+            Err(_) => 0.0,
+        };
+        last_frame_time = frame_start_time;
 
         // Event Handler
         for event in event_pump.poll_iter() {
@@ -100,20 +224,110 @@ fn main() {
                 Event::Window { win_event, .. } => {
                     if let WindowEvent::Resized(x, y) = win_event {
                         renderer.resize(x as usize, y as usize);
+                        frame_texture = texture_creator.create_texture_streaming(PixelFormatEnum::RGB24, x as u32, y as u32).unwrap();
                     }
                 },
-                Event::KeyDown { keycode: key, .. } => {
+                Event::KeyDown { keycode: key, keymod, .. } => {
+                    let ctrl = keymod.intersects(LCTRLMOD | RCTRLMOD);
                     match key {
                         Some(Keycode::Escape) => break 'running,
-                        Some(Keycode::P) => {
-                            renderer.toggle_projection_mode();
-                            if let Some(text) = text_map.get_mut(&TextID::Projection) {
-                                text.set_text(&font, &texture_creator, &format!("(P)rojection: {}", renderer.projection_mode_str()), Color::RGBA(255, 0, 0, 255))
+                        Some(Keycode::S) if ctrl => {
+                            let scene_path = settings.scene_path().unwrap_or_else(|| Path::new("scene.toml"));
+                            renderer.save_scene(scene_path);
+                            println!("Saved scene: {:?}", scene_path);
+                        },
+                        Some(Keycode::P) => renderer.toggle_projection_mode(),
+                        Some(Keycode::C) => {
+                            renderer.toggle_camera_mode();
+                            if let Some(text) = text_map.get_mut(&TextID::CameraMode) {
+                                text.set_text(&font, &texture_creator, &format!("(C)amera: {}", renderer.camera_mode_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::Tab) => {
+                            renderer.select_next();
+                            if let Some(text) = text_map.get_mut(&TextID::Selection) {
+                                text.set_text(&font, &texture_creator, &format!("(Tab) Selected: {}", renderer.selected_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::Z) => {
+                            renderer.toggle_dolly_zoom();
+                            if let Some(text) = text_map.get_mut(&TextID::ZoomMode) {
+                                text.set_text(&font, &texture_creator, &format!("(Z)oom: {}", renderer.zoom_mode_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::M) => {
+                            renderer.cycle_msaa();
+                            if let Some(text) = text_map.get_mut(&TextID::Msaa) {
+                                text.set_text(&font, &texture_creator, &format!("(M)SAA: {}", renderer.msaa_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::X) => {
+                            renderer.toggle_supersampling();
+                            if let Some(text) = text_map.get_mut(&TextID::Supersampling) {
+                                text.set_text(&font, &texture_creator, &format!("(X) Supersampling: {}", renderer.supersampling_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::V) => {
+                            renderer.toggle_fxaa();
+                            if let Some(text) = text_map.get_mut(&TextID::Fxaa) {
+                                text.set_text(&font, &texture_creator, &format!("(V) FXAA: {}", renderer.fxaa_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::N) => {
+                            renderer.cycle_debug_view();
+                            if let Some(text) = text_map.get_mut(&TextID::DebugView) {
+                                text.set_text(&font, &texture_creator, &format!("(N) View: {}", renderer.debug_view_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::O) => {
+                            renderer.toggle_order_independent_transparency();
+                            if let Some(text) = text_map.get_mut(&TextID::Transparency) {
+                                text.set_text(&font, &texture_creator, &format!("(O) Transparency: {}", renderer.transparency_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::T) => {
+                            renderer.cycle_shading();
+                            if let Some(text) = text_map.get_mut(&TextID::Shading) {
+                                text.set_text(&font, &texture_creator, &format!("(T) Shading: {}", renderer.shading_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::G) => {
+                            renderer.toggle_ssao();
+                            if let Some(text) = text_map.get_mut(&TextID::Ssao) {
+                                text.set_text(&font, &texture_creator, &format!("(G) SSAO: {}", renderer.ssao_str()), Color::RGBA(255, 0, 0, 255));
+                            }
+                        },
+                        Some(Keycode::B) => {
+                            renderer.toggle_fog();
+                            if let Some(text) = text_map.get_mut(&TextID::Fog) {
+                                text.set_text(&font, &texture_creator, &format!("(B) Fog: {}", renderer.fog_str()), Color::RGBA(255, 0, 0, 255));
                             }
                         },
+                        Some(Keycode::F1) => renderer.set_standard_view(StandardView::Front),
+                        Some(Keycode::F2) => renderer.set_standard_view(StandardView::Back),
+                        Some(Keycode::F3) => renderer.set_standard_view(StandardView::Left),
+                        Some(Keycode::F4) => renderer.set_standard_view(StandardView::Right),
+                        Some(Keycode::F5) => renderer.set_standard_view(StandardView::Top),
+                        Some(Keycode::F6) => renderer.set_standard_view(StandardView::Bottom),
+                        Some(keycode) if bookmark_slot(keycode).is_some() => {
+                            let slot = bookmark_slot(keycode).unwrap();
+                            if let Some(ref mut bookmarks) = bookmarks {
+                                if ctrl {
+                                    bookmarks.set(slot, renderer.camera_description());
+                                } else if let Some(camera) = bookmarks.get(slot) {
+                                    renderer.restore_camera(camera);
+                                }
+                            }
+                        },
+                        Some(Keycode::Home) => renderer.frame_all(),
+                        Some(Keycode::F12) => {
+                            let path = screenshot::save(&mut renderer, settings.screenshot_scale());
+                            println!("Saved screenshot: {:?}", path);
+                        },
+                        Some(Keycode::F) => renderer.frame_selected(),
                         Some(Keycode::Equals) => renderer.increase_ambient_intensity(0.1),
                         Some(Keycode::Minus) => renderer.increase_ambient_intensity(-0.1),
-                        Some(Keycode::S) => {
+                        Some(Keycode::S) if !renderer.is_fly_camera() => {
                             renderer.toggle_smooth_shading();
                             if let Some(text) = text_map.get_mut(&TextID::SmoothShading) {
                                 text.set_text(&font, &texture_creator, &format!("(S)mooth Shading: {}", renderer.smooth_shading_str()), Color::RGBA(255, 0, 0, 255));
@@ -121,32 +335,91 @@ fn main() {
                         },
                         _ => (),
                     }
+
+                    // Standard views and bookmarks can also change the projection.
+                    if let Some(text) = text_map.get_mut(&TextID::Projection) {
+                        text.set_text(&font, &texture_creator, &format!("(P)rojection: {}", renderer.projection_mode_str()), Color::RGBA(255, 0, 0, 255));
+                    }
                 },
                 Event::MouseWheel { y: mouse_y, .. } => {
                     let zoom_sensitivity = 4.0;
                     renderer.zoom_camera(-mouse_y as f32 * zoom_sensitivity);
                 },
                 Event::MouseButtonDown { mouse_btn, .. } => {
-                    if mouse_btn == MouseButton::Left {
-                        mouse_down = true;
+                    match mouse_btn {
+                        MouseButton::Left => orbit_down = true,
+                        MouseButton::Middle => pan_down = true,
+                        MouseButton::Right => dolly_down = true,
+                        _ => (),
                     }
                 },
                 Event::MouseButtonUp { mouse_btn, .. } => {
-                    if mouse_btn == MouseButton::Left {
-                        mouse_down = false;
+                    match mouse_btn {
+                        MouseButton::Left => orbit_down = false,
+                        MouseButton::Middle => pan_down = false,
+                        MouseButton::Right => dolly_down = false,
+                        _ => (),
                     }
                 },
-                Event::MouseMotion { xrel: delta_x, .. } => {
-                    if mouse_down {
+                Event::MouseMotion { xrel: delta_x, yrel: delta_y, .. } => {
+                    if orbit_down {
+                        let sensitivity = 0.01;
+                        if renderer.is_fly_camera() {
+                            renderer.look(delta_x as f32 * sensitivity, delta_y as f32 * sensitivity);
+                        } else {
+                            renderer.orbit(delta_x as f32 * sensitivity, delta_y as f32 * sensitivity);
+                        }
+                    }
+
+                    if pan_down {
+                        let sensitivity = 0.002;
+                        renderer.pan(delta_x as f32 * sensitivity, delta_y as f32 * sensitivity);
+                    }
+
+                    if dolly_down {
                         let sensitivity = 0.01;
-                        renderer.orbit(delta_x as f32 * sensitivity, 0.0);
+                        renderer.dolly(delta_y as f32 * sensitivity);
                     }
                 }
                 _ => {}
             }
         }
 
-        renderer.render(&mut canvas);
+        if renderer.is_fly_camera() {
+            let keyboard = event_pump.keyboard_state();
+            // Ctrl shortcuts such as Ctrl+S share their keys with movement.
+            let ctrl = keyboard.is_scancode_pressed(Scancode::LCtrl) || keyboard.is_scancode_pressed(Scancode::RCtrl);
+            let axis = |positive: Scancode, negative: Scancode| {
+                let mut value = 0.0;
+                if keyboard.is_scancode_pressed(positive) {
+                    value += 1.0;
+                }
+                if keyboard.is_scancode_pressed(negative) {
+                    value -= 1.0;
+                }
+                value
+            };
+
+            let speed = if keyboard.is_scancode_pressed(Scancode::LShift) || keyboard.is_scancode_pressed(Scancode::RShift) {
+                fly_speed * 4.0
+            } else if keyboard.is_scancode_pressed(Scancode::LAlt) || keyboard.is_scancode_pressed(Scancode::RAlt) {
+                fly_speed * 0.25
+            } else {
+                fly_speed
+            };
+
+            let distance = speed * delta_time;
+            if !ctrl {
+                renderer.fly(axis(Scancode::D, Scancode::A) * distance, axis(Scancode::E, Scancode::Q) * distance, axis(Scancode::W, Scancode::S) * distance);
+            }
+        }
+
+        renderer.update(delta_time);
+        renderer.render();
+
+        let framebuffer = renderer.framebuffer();
+        frame_texture.update(None, framebuffer.pixels(), framebuffer.width() * 3).unwrap();
+        canvas.copy(&frame_texture, None, None).unwrap();
 
         for (_, text) in text_map.iter() {
             match text.texture() {
@@ -171,6 +444,86 @@ fn main() {
     }
 }
 
+/// Returns a new `Renderer` with the models, materials and camera settings from `settings`.
+fn create_renderer(settings: &Settings, command_line_processor: &CommandLineProcessor) -> Renderer {
+    let mut renderer = Renderer::new(settings.width() as usize, settings.height() as usize);
+    if let Some(scene_path) = settings.scene_path() {
+        renderer.load_scene(scene_path);
+    }
+    if let Some(model_path) = settings.model_path() {
+        renderer.load_models(vec!(model_path));
+        if settings.scene_path().is_none() {
+            renderer.frame_all();
+        }
+    }
 
+    if let Some(fov) = settings.fov() {
+        renderer.set_fov(fov);
+    }
+    if let Some(scale) = settings.ortho_scale() {
+        renderer.set_ortho_scale(scale);
+    }
+    if settings.near().is_some() || settings.far().is_some() {
+        let (near, far) = renderer.clip_planes();
+        renderer.set_clip_planes(settings.near().unwrap_or(near), settings.far().unwrap_or(far));
+    }
+    renderer.set_dolly_zoom(settings.dolly_zoom());
+    renderer.set_transition_duration(settings.transition_time());
+    renderer.set_msaa_samples(settings.msaa_samples() as usize);
+    renderer.set_render_scale(settings.render_scale() as usize);
+    renderer.set_downsample(settings.downsample());
+    renderer.set_fxaa(settings.fxaa());
+    renderer.set_debug_view(settings.debug_view());
+    renderer.set_order_independent_transparency(settings.order_independent_transparency());
+    renderer.set_shading(settings.shading());
+    renderer.set_toon(settings.toon());
+    renderer.set_ssao(settings.ssao());
+    renderer.set_ssao_enabled(settings.ssao_enabled());
+    renderer.set_fog(settings.fog());
+    renderer.set_fog_enabled(settings.fog_enabled());
+    if let Some(color) = settings.fog_color() {
+        renderer.set_background(Color::RGB(color[0], color[1], color[2]));
+    }
+    renderer.finish_transition();
 
+    if let ParameterValue::Path(material_path) = command_line_processor.get_parameter_value("material") {
+       renderer.load_material(material_path);
+    }
+
+    if let ParameterValue::Path(matcap_path) = command_line_processor.get_parameter_value("matcap") {
+        renderer.load_matcap(matcap_path);
+    }
 
+    renderer
+}
+
+/// Renders a single frame to the image at `path` along with the AOV passes in `settings`.
+fn render_image(renderer: &mut Renderer, path: &Path, settings: &Settings) {
+    if !settings.aovs().is_empty() {
+        renderer.enable_aovs();
+    }
+
+    renderer.render();
+    renderer.framebuffer().to_image().save(path).unwrap_or_else(|_| panic!("Unable to write image: {:?}", path));
+
+    if let Some(aovs) = renderer.aovs() {
+        let (near, far) = renderer.clip_planes();
+        aovs.write(path, settings.aovs(), settings.depth_format(), near, far);
+    }
+}
+
+/// Returns the camera bookmark slot for the number keys 1 to 9.
+fn bookmark_slot(keycode: Keycode) -> Option<u32> {
+    match keycode {
+        Keycode::Num1 => Some(1),
+        Keycode::Num2 => Some(2),
+        Keycode::Num3 => Some(3),
+        Keycode::Num4 => Some(4),
+        Keycode::Num5 => Some(5),
+        Keycode::Num6 => Some(6),
+        Keycode::Num7 => Some(7),
+        Keycode::Num8 => Some(8),
+        Keycode::Num9 => Some(9),
+        _ => None,
+    }
+}
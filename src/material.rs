@@ -1,26 +1,180 @@
 //! Material
 
 extern crate image;
+extern crate tdmath;
 
 use image::DynamicImage;
+use self::tdmath::Vector3;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// Shininess of materials with a specular map but no `shininess` set.
+const DEFAULT_SHININESS: f32 = 32.0;
+
+/// Which triangle faces are not drawn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CullMode {
+    /// Faces pointing away from the camera are culled
+    Back,
+    /// Faces pointing towards the camera are culled
+    Front,
+    /// Both sides are drawn
+    None,
+}
+
+impl CullMode {
+    /// Returns the `CullMode` with the config name `name`.
+    pub fn from_name(name: &str) -> Option<CullMode> {
+        match name {
+            "back" => Some(CullMode::Back),
+            "front" => Some(CullMode::Front),
+            "none" => Some(CullMode::None),
+            _ => None,
+        }
+    }
+}
+
+/// How a material's alpha is used.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AlphaMode {
+    /// Alpha is ignored
+    Opaque,
+    /// Fragments with alpha below `alpha_cutoff` are discarded, the rest are opaque
+    Mask,
+    /// Drawn after opaque triangles and blended over them without writing depth
+    Blend,
+}
+
+impl AlphaMode {
+    /// Returns the `AlphaMode` with the config name `name`.
+    pub fn from_name(name: &str) -> Option<AlphaMode> {
+        match name {
+            "opaque" => Some(AlphaMode::Opaque),
+            "mask" => Some(AlphaMode::Mask),
+            "blend" => Some(AlphaMode::Blend),
+            _ => None,
+        }
+    }
+}
+
+/// Vertex order of front facing triangles as seen from the camera.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Winding {
+    CounterClockwise,
+    Clockwise,
+}
+
+impl Winding {
+    /// Returns the `Winding` with the config name `name`.
+    pub fn from_name(name: &str) -> Option<Winding> {
+        match name {
+            "ccw" => Some(Winding::CounterClockwise),
+            "cw" => Some(Winding::Clockwise),
+            _ => None,
+        }
+    }
+}
 
 /// Material
 pub struct Material {
     pub albedo: Option<Box<DynamicImage>>,
+    /// Multiplied with `specular`
+    pub specular_map: Option<Box<DynamicImage>>,
+    /// Tangent space normal map with +y pointing up the texture
+    pub normal_map: Option<Box<DynamicImage>>,
+    pub diffuse: Vector3,
+    /// Color of the specular highlight
+    pub specular: Vector3,
+    /// Blinn-Phong exponent, 0 disables the specular highlight
+    pub shininess: f32,
+    pub opacity: f32,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub cull_mode: CullMode,
+    pub winding: Winding,
+    path: Option<PathBuf>,
 }
 
 impl Material {
     /// Returns a new `Material` from a map of texture types and texture paths.
     pub fn from_hashmap(material_map: HashMap<String, String>) -> Material {
-        let albedo = match material_map.get("albedo") {
-            Some(albedo_path) => Some(Box::new(image::open(albedo_path).unwrap_or_else(|_| panic!("Cannot load texture: {}", albedo_path)))),
+        let load_texture = |texture_type: &str| match material_map.get(texture_type) {
+            Some(texture_path) => Some(Box::new(image::open(texture_path).unwrap_or_else(|_| panic!("Cannot load texture: {}", texture_path)))),
             None => None,
         };
 
         Material {
-            albedo,
+            albedo: load_texture("albedo"),
+            specular_map: load_texture("specular"),
+            normal_map: load_texture("normal"),
+            ..Material::default()
+        }
+    }
+
+    /// Returns a new `Material` loaded from the TOML file at `material_path`.
+    /// Texture paths are relative to the material file.
+    pub fn load(material_path: &Path) -> Material {
+        let mut f = File::open(material_path).unwrap_or_else(|_| panic!("File not found: {:?}", material_path));
+        let mut file_contents = String::new();
+        f.read_to_string(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", material_path));
+
+        let toml = file_contents.parse::<Value>().unwrap_or_else(|_| panic!("Unable to parse material: {:?}", material_path));
+
+        let mut material_map = HashMap::new();
+
+        for texture_type in &["albedo", "specular", "normal"] {
+            if let Some(texture_path) = toml.get(*texture_type).and_then(|t| t.as_str()) {
+                let mut full_path = PathBuf::new();
+                full_path.push(material_path);
+                full_path.pop(); // Remove material name
+                full_path.push(texture_path);
+                material_map.insert((*texture_type).to_owned(), full_path.to_str().unwrap().to_owned());
+            }
+        }
+
+        let mut material = Material::from_hashmap(material_map);
+        material.path = Some(material_path.to_path_buf());
+
+        // A specular map alone gives a white highlight.
+        if material.specular_map.is_some() {
+            material.specular = Vector3::new(1.0, 1.0, 1.0);
+            material.shininess = DEFAULT_SHININESS;
+        }
+        if let Some(color) = toml.get("specular_color").and_then(|c| c.as_array()) {
+            let channel = |i: usize| color.get(i).and_then(|c| c.as_float().or_else(|| c.as_integer().map(|i| i as f64))).unwrap_or(0.0) as f32;
+            material.specular = Vector3::new(channel(0), channel(1), channel(2));
+        }
+        if let Some(shininess) = toml.get("shininess").and_then(|s| s.as_float().or_else(|| s.as_integer().map(|i| i as f64))) {
+            material.shininess = shininess as f32;
         }
+
+        if let Some(opacity) = toml.get("opacity").and_then(|o| o.as_float().or_else(|| o.as_integer().map(|i| i as f64))) {
+            material.opacity = opacity as f32;
+        }
+        if let Some(alpha) = toml.get("alpha").and_then(|a| a.as_str()) {
+            material.alpha_mode = AlphaMode::from_name(alpha).unwrap_or_else(|| panic!("Unknown alpha mode: {}", alpha));
+        } else if material.opacity < 1.0 {
+            material.alpha_mode = AlphaMode::Blend;
+        }
+        if let Some(cutoff) = toml.get("alpha_cutoff").and_then(|c| c.as_float()) {
+            material.alpha_cutoff = cutoff as f32;
+        }
+        if let Some(cull) = toml.get("cull").and_then(|c| c.as_str()) {
+            material.cull_mode = CullMode::from_name(cull).unwrap_or_else(|| panic!("Unknown cull mode: {}", cull));
+        }
+        if let Some(winding) = toml.get("winding").and_then(|w| w.as_str()) {
+            material.winding = Winding::from_name(winding).unwrap_or_else(|| panic!("Unknown winding order: {}", winding));
+        }
+
+        material
+    }
+
+    /// Returns the path of the file this material was loaded from.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref().map(|p| p.as_path())
     }
 }
 
@@ -28,6 +182,17 @@ impl Default for Material {
     fn default() -> Self {
         Material {
             albedo: None,
+            specular_map: None,
+            normal_map: None,
+            diffuse: Vector3::new(0.5, 0.5, 0.5),
+            specular: Vector3::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            opacity: 1.0,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            cull_mode: CullMode::Back,
+            winding: Winding::CounterClockwise,
+            path: None,
         }
     }
-}
\ No newline at end of file
+}
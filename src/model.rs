@@ -3,8 +3,13 @@
 use std::fs::File;
 use std::io::prelude::*;
 use tdmath::{Vector3, Vector4};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use modelloader::*;
+use stl::parse_stl;
+use ply::parse_ply;
+use material::Material;
+use mtl::load_mtl;
+use bounds::BoundingSphere;
 
 /// Triangle
 #[derive(Debug, Copy, Clone)]
@@ -18,22 +23,82 @@ pub struct Triangle {
     pub vn0: Vector3,
     pub vn1: Vector3,
     pub vn2: Vector3,
+    pub vc0: Vector3,
+    pub vc1: Vector3,
+    pub vc2: Vector3,
+}
+
+/// Range of a model's triangles that share a material.
+#[derive(Debug, Clone)]
+pub struct Submesh {
+    pub start: usize,
+    pub end: usize,
+    pub material: Option<usize>,
 }
 
 /// Model
 pub struct Model {
-    triangles: Vec<Triangle>
+    triangles: Vec<Triangle>,
+    submeshes: Vec<Submesh>,
+    materials: Vec<Material>,
+    has_vertex_colors: bool,
+    material: Option<Material>,
+    path: PathBuf,
+    bounding_sphere: Option<BoundingSphere>,
 }
 
 impl Model {
     /// Returns a new `Model` loaded from the filepath specified.
+    /// The file format is selected by the extension: `.stl`, `.ply` or `.obj`.
     pub fn new(filepath: &Path) -> Model {
         let mut f = File::open(filepath).unwrap_or_else(|_| panic!("File not found: {:?}", filepath));
-        let mut file_contents = String::new();
-        f.read_to_string(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", filepath));
+        let mut file_contents = Vec::new();
+        f.read_to_end(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", filepath));
+
+        let extension = filepath.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        let mut model = Model {
+            triangles: Vec::new(),
+            submeshes: Vec::new(),
+            materials: Vec::new(),
+            has_vertex_colors: false,
+            material: None,
+            path: filepath.to_path_buf(),
+            bounding_sphere: None,
+        };
+
+        match extension.as_ref().map(|e| e.as_str()) {
+            Some("stl") => model.triangles = parse_stl(&file_contents),
+            Some("ply") => {
+                let (triangles, has_vertex_colors) = parse_ply(&file_contents);
+                model.triangles = triangles;
+                model.has_vertex_colors = has_vertex_colors;
+            },
+            _ => {
+                let file_contents = String::from_utf8(file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", filepath));
+                model.triangles = Model::parse_obj(&file_contents);
+                model.load_obj_materials(&file_contents, filepath);
+            },
+        }
+
+        if model.submeshes.is_empty() {
+            model.submeshes.push(Submesh { start: 0, end: model.triangles.len(), material: None });
+        }
+
+        let positions: Vec<Vector3> = model.triangles.iter()
+            .flat_map(|t| vec![t.v0.xyz(), t.v1.xyz(), t.v2.xyz()])
+            .collect();
+        model.bounding_sphere = BoundingSphere::from_points(&positions);
+
+        model
+    }
 
+    /// Returns the triangles in the contents of an OBJ file.
+    fn parse_obj(file_contents: &str) -> Vec<Triangle> {
         let mut triangles = Vec::new();
-        let m = parse_obj_file(&file_contents);
+        let m = parse_obj_file(file_contents);
         for v in m.vertices.chunks(3) {
             let t = Triangle {
                 v0: Vector4::new(v[0].p.x, v[0].p.y, v[0].p.z, 1.0),
@@ -45,16 +110,118 @@ impl Model {
                 vn0: v[0].normal,
                 vn1: v[1].normal,
                 vn2: v[2].normal,
+                vc0: Vector3::new(1.0, 1.0, 1.0),
+                vc1: Vector3::new(1.0, 1.0, 1.0),
+                vc2: Vector3::new(1.0, 1.0, 1.0),
             };
 
             triangles.push(t);
         }
 
-        Model { triangles }
+        triangles
+    }
+
+    /// Loads the `mtllib` materials of an OBJ file and splits the triangles into a submesh per `usemtl` group.
+    /// Polygons are assumed to be triangulated as fans, producing `n - 2` triangles for `n` vertices.
+    fn load_obj_materials(&mut self, file_contents: &str, filepath: &Path) {
+        let obj_dir = filepath.parent().unwrap_or_else(|| Path::new(""));
+        let mut material_names = Vec::new();
+        let mut submeshes: Vec<Submesh> = Vec::new();
+        let mut triangle_count = 0;
+
+        for line in file_contents.lines() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("mtllib") => {
+                    let mtl_name = tokens.collect::<Vec<&str>>().join(" ");
+                    let mtl_path = obj_dir.join(&mtl_name);
+                    if mtl_path.is_file() {
+                        for (name, material) in load_mtl(&mtl_path) {
+                            material_names.push(name);
+                            self.materials.push(material);
+                        }
+                    } else {
+                        eprintln!("Material library not found: {:?}", mtl_path);
+                    }
+                },
+                Some("usemtl") => {
+                    let name = tokens.collect::<Vec<&str>>().join(" ");
+                    let material = material_names.iter().position(|n| *n == name);
+                    if let Some(submesh) = submeshes.last_mut() {
+                        submesh.end = triangle_count;
+                    }
+                    submeshes.push(Submesh { start: triangle_count, end: triangle_count, material });
+                },
+                Some("f") => triangle_count += tokens.count().saturating_sub(2),
+                _ => (),
+            }
+        }
+
+        if !submeshes.is_empty() && triangle_count != self.triangles.len() {
+            eprintln!("Unable to match material groups to triangles in {:?}", filepath);
+            submeshes.clear();
+        }
+
+        if let Some(submesh) = submeshes.last_mut() {
+            submesh.end = triangle_count;
+        }
+
+        // Faces declared before the first usemtl use the default material.
+        if let Some(start) = submeshes.first().map(|s| s.start) {
+            if start > 0 {
+                submeshes.insert(0, Submesh { start: 0, end: start, material: None });
+            }
+        }
+
+        self.submeshes = submeshes.into_iter().filter(|s| s.end > s.start).collect();
     }
 
     /// Returns a list of the model's triangles.
     pub fn triangles(&self) -> &Vec<Triangle> {
         &self.triangles
     }
+
+    /// Returns a list of the model's submeshes.
+    pub fn submeshes(&self) -> &Vec<Submesh> {
+        &self.submeshes
+    }
+
+    /// Returns the triangles of `submesh`.
+    pub fn submesh_triangles(&self, submesh: &Submesh) -> &[Triangle] {
+        &self.triangles[submesh.start..submesh.end]
+    }
+
+    /// Returns the material used to draw `submesh`.
+    /// A material set with `set_material` overrides the materials loaded with the model.
+    pub fn submesh_material(&self, submesh: &Submesh) -> Option<&Material> {
+        match self.material {
+            Some(ref material) => Some(material),
+            None => submesh.material.map(|i| &self.materials[i]),
+        }
+    }
+
+    /// Returns the material assigned to the whole model.
+    pub fn material(&self) -> Option<&Material> {
+        self.material.as_ref()
+    }
+
+    /// Sets the material of the whole model, overriding the materials loaded with the model.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = Some(material);
+    }
+
+    /// Returns the path of the file this model was loaded from.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Returns the model space bounding sphere, or None if the model has no triangles.
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.bounding_sphere
+    }
+
+    /// Returns true if the model's triangles have vertex colors.
+    pub fn has_vertex_colors(&self) -> bool {
+        self.has_vertex_colors
+    }
 }
@@ -0,0 +1,186 @@
+//! MTL Loader
+
+extern crate tdmath;
+extern crate image;
+
+use self::tdmath::Vector3;
+use image::DynamicImage;
+use material::{Material, AlphaMode};
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Loads the materials in the MTL file at `mtl_path`.
+/// Returns a list of material names and materials in the order they are declared.
+/// Texture paths are relative to the MTL file.
+/// A missing or unreadable file is reported and loads no materials, so the model is drawn with the default material.
+pub fn load_mtl(mtl_path: &Path) -> Vec<(String, Material)> {
+    let mut file_contents = String::new();
+    let read = File::open(mtl_path).and_then(|mut f| f.read_to_string(&mut file_contents));
+    if read.is_err() {
+        eprintln!("Cannot read material library: {:?}", mtl_path);
+        return Vec::new();
+    }
+
+    let mtl_dir = mtl_path.parent().unwrap_or_else(|| Path::new(""));
+    parse_mtl(&file_contents, mtl_dir)
+}
+
+/// Parses the contents of an MTL file with texture paths relative to `mtl_dir`.
+fn parse_mtl(contents: &str, mtl_dir: &Path) -> Vec<(String, Material)> {
+    let mut materials: Vec<(String, Material)> = Vec::new();
+
+    for line in contents.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.is_empty() || tokens[0].starts_with('#') {
+            continue;
+        }
+
+        if tokens[0] == "newmtl" {
+            let name = tokens[1..].join(" ");
+            materials.push((name, Material::default()));
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(entry) => &mut entry.1,
+            None => continue,
+        };
+
+        match tokens[0] {
+            "Kd" => material.diffuse = parse_color(&tokens),
+            "Ks" => material.specular = parse_color(&tokens),
+            "Ns" => material.shininess = parse_scalar(&tokens, material.shininess),
+            "d" => material.opacity = parse_scalar(&tokens, material.opacity),
+            "Tr" => material.opacity = 1.0 - parse_scalar(&tokens, 1.0 - material.opacity),
+            "map_Kd" => material.albedo = load_texture(&tokens, mtl_dir),
+            "map_Ks" => material.specular_map = load_texture(&tokens, mtl_dir),
+            "map_Bump" | "map_bump" | "bump" | "norm" => material.normal_map = load_texture(&tokens, mtl_dir),
+            _ => (),
+        }
+    }
+
+    // Partially transparent materials are blended over the rest of the scene.
+    for entry in &mut materials {
+        if entry.1.opacity < 1.0 {
+            entry.1.alpha_mode = AlphaMode::Blend;
+        }
+    }
+
+    materials
+}
+
+/// Parses the `r g b` values of a color statement.
+fn parse_color(tokens: &[&str]) -> Vector3 {
+    let values: Vec<f32> = tokens[1..].iter().filter_map(|t| t.parse().ok()).collect();
+    match values.len() {
+        0 => Vector3::new(0.0, 0.0, 0.0),
+        1 | 2 => Vector3::new(values[0], values[0], values[0]),
+        _ => Vector3::new(values[0], values[1], values[2]),
+    }
+}
+
+/// Parses the value of a scalar statement.
+fn parse_scalar(tokens: &[&str], default: f32) -> f32 {
+    tokens.get(1).and_then(|t| t.parse().ok()).unwrap_or(default)
+}
+
+/// Loads the texture referenced by a texture map statement.
+/// Textures that cannot be loaded are reported and skipped.
+fn load_texture(tokens: &[&str], mtl_dir: &Path) -> Option<Box<DynamicImage>> {
+    let texture_path = texture_path(tokens, mtl_dir)?;
+    match image::open(&texture_path) {
+        Ok(texture) => Some(Box::new(texture)),
+        Err(_) => {
+            eprintln!("Cannot load texture: {:?}", texture_path);
+            None
+        },
+    }
+}
+
+/// Returns the path of the texture in a texture map statement.
+/// Texture options such as `-bm 1.0` are skipped and the file name is taken from the last token.
+fn texture_path(tokens: &[&str], mtl_dir: &Path) -> Option<PathBuf> {
+    match tokens.last() {
+        Some(name) if tokens.len() > 1 => Some(mtl_dir.join(name.replace('\\', "/"))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors_with_one_or_three_values() {
+        assert_eq!(vector(parse_color(&["Kd", "0.1", "0.2", "0.3"])), (0.1, 0.2, 0.3));
+        assert_eq!(vector(parse_color(&["Ks", "0.5"])), (0.5, 0.5, 0.5));
+        assert_eq!(vector(parse_color(&["Kd"])), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn parses_materials_in_order() {
+        let materials = parse_mtl("# comment
+newmtl first
+Kd 1 0 0
+Ks 0.5
+Ns 96
+newmtl second material
+Kd 0 1 0
+", Path::new(""));
+
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].0, "first");
+        assert_eq!(vector(materials[0].1.diffuse), (1.0, 0.0, 0.0));
+        assert_eq!(vector(materials[0].1.specular), (0.5, 0.5, 0.5));
+        assert_eq!(materials[0].1.shininess, 96.0);
+        assert_eq!(materials[1].0, "second material");
+        assert_eq!(vector(materials[1].1.diffuse), (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn dissolve_and_transparency_are_inverse() {
+        let materials = parse_mtl("newmtl dissolve
+d 0.25
+newmtl transparency
+Tr 0.25
+newmtl opaque
+d 1.0
+", Path::new(""));
+
+        assert_eq!(materials[0].1.opacity, 0.25);
+        assert_eq!(materials[0].1.alpha_mode, AlphaMode::Blend);
+        assert_eq!(materials[1].1.opacity, 0.75);
+        assert_eq!(materials[1].1.alpha_mode, AlphaMode::Blend);
+        assert_eq!(materials[2].1.alpha_mode, AlphaMode::Opaque);
+    }
+
+    #[test]
+    fn texture_paths_skip_options() {
+        let dir = Path::new("models");
+
+        assert_eq!(texture_path(&["map_Kd", "albedo.png"], dir), Some(dir.join("albedo.png")));
+        assert_eq!(texture_path(&["map_Bump", "-bm", "1.0", "normal.png"], dir), Some(dir.join("normal.png")));
+        assert_eq!(texture_path(&["map_Kd", "-o", "0.5", "0.5", "0", "-s", "2", "2", "1", "tex.png"], dir), Some(dir.join("tex.png")));
+        assert_eq!(texture_path(&["map_Kd", "textures\\wood.png"], dir), Some(dir.join("textures/wood.png")));
+        assert_eq!(texture_path(&["map_Kd"], dir), None);
+    }
+
+    #[test]
+    fn missing_textures_are_skipped() {
+        let materials = parse_mtl("newmtl missing
+map_Kd does_not_exist.png
+", Path::new(""));
+
+        assert!(materials[0].1.albedo.is_none());
+    }
+
+    #[test]
+    fn missing_library_loads_no_materials() {
+        assert!(load_mtl(Path::new("does_not_exist.mtl")).is_empty());
+    }
+
+    fn vector(v: Vector3) -> (f32, f32, f32) {
+        (v.x, v.y, v.z)
+    }
+}
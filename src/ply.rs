@@ -0,0 +1,471 @@
+//! PLY Loader
+
+use tdmath::{Vector3, Vector4};
+use model::Triangle;
+use std::str;
+
+/// PLY data encoding
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// PLY scalar property type
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    /// Returns the `ScalarType` for a PLY type name.
+    fn from_name(name: &str) -> ScalarType {
+        match name {
+            "char" | "int8" => ScalarType::Int8,
+            "uchar" | "uint8" => ScalarType::UInt8,
+            "short" | "int16" => ScalarType::Int16,
+            "ushort" | "uint16" => ScalarType::UInt16,
+            "int" | "int32" => ScalarType::Int32,
+            "uint" | "uint32" => ScalarType::UInt32,
+            "float" | "float32" => ScalarType::Float32,
+            "double" | "float64" => ScalarType::Float64,
+            _ => panic!("Unknown PLY property type: {}", name),
+        }
+    }
+
+    /// Returns the size of the type in bytes.
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    /// Returns the value used to normalize colors of this type to the 0-1 range.
+    fn color_scale(self) -> f32 {
+        match self {
+            ScalarType::UInt8 => 255.0,
+            ScalarType::UInt16 => 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// PLY element property
+#[derive(Debug)]
+enum Property {
+    Scalar(String, ScalarType),
+    List(String, ScalarType, ScalarType),
+}
+
+/// PLY element declared in the header
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values from the body of a PLY file.
+struct Reader<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the next value as an `f64`.
+    fn read(&mut self, scalar_type: ScalarType) -> f64 {
+        match self.format {
+            Format::Ascii => self.read_ascii(),
+            _ => self.read_binary(scalar_type),
+        }
+    }
+
+    /// Reads the next whitespace separated token.
+    fn read_ascii(&mut self) -> f64 {
+        while self.position < self.bytes.len() && self.bytes[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+
+        let start = self.position;
+        while self.position < self.bytes.len() && !self.bytes[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+
+        let token = str::from_utf8(&self.bytes[start..self.position]).unwrap_or("");
+        token.parse().unwrap_or_else(|_| panic!("Invalid PLY value: {}", token))
+    }
+
+    /// Reads the next binary value of `scalar_type`.
+    fn read_binary(&mut self, scalar_type: ScalarType) -> f64 {
+        let size = scalar_type.size();
+        if self.position + size > self.bytes.len() {
+            panic!("Unexpected end of PLY file");
+        }
+
+        let mut raw = 0u64;
+        for i in 0..size {
+            let byte = match self.format {
+                Format::BinaryBigEndian => self.bytes[self.position + i],
+                _ => self.bytes[self.position + size - 1 - i],
+            };
+            raw = raw << 8 | u64::from(byte);
+        }
+        self.position += size;
+
+        match scalar_type {
+            ScalarType::Int8 => f64::from(raw as u8 as i8),
+            ScalarType::UInt8 => f64::from(raw as u8),
+            ScalarType::Int16 => f64::from(raw as u16 as i16),
+            ScalarType::UInt16 => f64::from(raw as u16),
+            ScalarType::Int32 => f64::from(raw as u32 as i32),
+            ScalarType::UInt32 => f64::from(raw as u32),
+            ScalarType::Float32 => f64::from(f32::from_bits(raw as u32)),
+            ScalarType::Float64 => f64::from_bits(raw),
+        }
+    }
+}
+
+/// PLY vertex attributes
+struct Vertex {
+    position: Vector3,
+    normal: Option<Vector3>,
+    uv: Vector3,
+    color: Vector3,
+}
+
+/// Parses an ASCII or binary PLY file into a list of triangles.
+/// Returns the triangles and whether the file contained vertex colors.
+pub fn parse_ply(bytes: &[u8]) -> (Vec<Triangle>, bool) {
+    let (format, elements, body_start) = parse_header(bytes);
+    let mut reader = Reader { format, bytes, position: body_start };
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    let mut has_colors = false;
+
+    for element in &elements {
+        for _ in 0..element.count {
+            match element.name.as_str() {
+                "vertex" => {
+                    let (vertex, color) = read_vertex(&mut reader, &element.properties);
+                    has_colors |= color;
+                    vertices.push(vertex);
+                },
+                "face" => faces.push(read_face(&mut reader, &element.properties)),
+                _ => skip_element(&mut reader, &element.properties),
+            }
+        }
+    }
+
+    let normals = if vertices.iter().all(|v| v.normal.is_some()) {
+        vertices.iter().map(|v| v.normal.unwrap()).collect()
+    } else {
+        smooth_normals(&vertices, &faces)
+    };
+
+    let mut triangles = Vec::new();
+    for face in &faces {
+        // Triangulate polygons as a fan around the first vertex.
+        for i in 1..face.len().saturating_sub(1) {
+            let (i0, i1, i2) = (face[0], face[i], face[i + 1]);
+            if i0 >= vertices.len() || i1 >= vertices.len() || i2 >= vertices.len() {
+                panic!("PLY face references missing vertex");
+            }
+
+            let (a, b, c) = (&vertices[i0], &vertices[i1], &vertices[i2]);
+            triangles.push(Triangle {
+                v0: Vector4::new(a.position.x, a.position.y, a.position.z, 1.0),
+                v1: Vector4::new(b.position.x, b.position.y, b.position.z, 1.0),
+                v2: Vector4::new(c.position.x, c.position.y, c.position.z, 1.0),
+                vt0: a.uv,
+                vt1: b.uv,
+                vt2: c.uv,
+                vn0: normals[i0],
+                vn1: normals[i1],
+                vn2: normals[i2],
+                vc0: a.color,
+                vc1: b.color,
+                vc2: c.color,
+            });
+        }
+    }
+
+    (triangles, has_colors)
+}
+
+/// Parses the PLY header.
+/// Returns the data format, the declared elements and the offset of the body.
+fn parse_header(bytes: &[u8]) -> (Format, Vec<Element>, usize) {
+    if !bytes.starts_with(b"ply") {
+        panic!("Not a PLY file");
+    }
+
+    let mut format = Format::Ascii;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let line_end = match bytes[position..].iter().position(|&b| b == b'\n') {
+            Some(offset) => position + offset,
+            None => panic!("PLY header is missing end_header"),
+        };
+        let line = String::from_utf8_lossy(&bytes[position..line_end]);
+        position = line_end + 1;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first() {
+            Some(&"format") if tokens.len() > 1 => {
+                format = match tokens[1] {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    other => panic!("Unknown PLY format: {}", other),
+                };
+            },
+            Some(&"element") if tokens.len() > 2 => {
+                elements.push(Element {
+                    name: tokens[1].to_owned(),
+                    count: tokens[2].parse().unwrap_or_else(|_| panic!("Invalid PLY element count: {}", tokens[2])),
+                    properties: Vec::new(),
+                });
+            },
+            Some(&"property") if tokens.len() > 2 => {
+                let property = if tokens[1] == "list" && tokens.len() > 4 {
+                    Property::List(tokens[4].to_owned(), ScalarType::from_name(tokens[2]), ScalarType::from_name(tokens[3]))
+                } else {
+                    Property::Scalar(tokens[2].to_owned(), ScalarType::from_name(tokens[1]))
+                };
+
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(property),
+                    None => panic!("PLY property declared before element"),
+                }
+            },
+            Some(&"end_header") => break,
+            _ => (),
+        }
+    }
+
+    (format, elements, position)
+}
+
+/// Reads a vertex element.
+/// Returns the vertex and whether it contained a color.
+fn read_vertex(reader: &mut Reader, properties: &[Property]) -> (Vertex, bool) {
+    let mut position = Vector3::new(0.0, 0.0, 0.0);
+    let mut normal = Vector3::new(0.0, 0.0, 0.0);
+    let mut uv = Vector3::new(0.0, 0.0, 0.0);
+    let mut color = Vector3::new(1.0, 1.0, 1.0);
+    let mut has_normal = false;
+    let mut has_color = false;
+
+    for property in properties {
+        match *property {
+            Property::Scalar(ref name, scalar_type) => {
+                let value = reader.read(scalar_type) as f32;
+                match name.as_str() {
+                    "x" => position.x = value,
+                    "y" => position.y = value,
+                    "z" => position.z = value,
+                    "nx" => { normal.x = value; has_normal = true; },
+                    "ny" => { normal.y = value; has_normal = true; },
+                    "nz" => { normal.z = value; has_normal = true; },
+                    "u" | "s" | "texture_u" => uv.x = value,
+                    "v" | "t" | "texture_v" => uv.y = 1.0 - value,
+                    "red" | "r" => { color.x = value / scalar_type.color_scale(); has_color = true; },
+                    "green" | "g" => { color.y = value / scalar_type.color_scale(); has_color = true; },
+                    "blue" | "b" => { color.z = value / scalar_type.color_scale(); has_color = true; },
+                    _ => (),
+                }
+            },
+            Property::List(_, count_type, item_type) => {
+                let count = reader.read(count_type) as usize;
+                for _ in 0..count {
+                    reader.read(item_type);
+                }
+            },
+        }
+    }
+
+    let normal = if has_normal {
+        Some(normal.normalized())
+    } else {
+        None
+    };
+
+    (Vertex { position, normal, uv, color }, has_color)
+}
+
+/// Reads a face element and returns its vertex indices.
+fn read_face(reader: &mut Reader, properties: &[Property]) -> Vec<usize> {
+    let mut indices = Vec::new();
+
+    for property in properties {
+        match *property {
+            Property::Scalar(_, scalar_type) => {
+                reader.read(scalar_type);
+            },
+            Property::List(ref name, count_type, item_type) => {
+                let count = reader.read(count_type) as usize;
+                let is_indices = name == "vertex_indices" || name == "vertex_index";
+                for _ in 0..count {
+                    let index = reader.read(item_type) as usize;
+                    if is_indices {
+                        indices.push(index);
+                    }
+                }
+            },
+        }
+    }
+
+    indices
+}
+
+/// Reads and discards an element that is not used by the renderer.
+fn skip_element(reader: &mut Reader, properties: &[Property]) {
+    for property in properties {
+        match *property {
+            Property::Scalar(_, scalar_type) => {
+                reader.read(scalar_type);
+            },
+            Property::List(_, count_type, item_type) => {
+                let count = reader.read(count_type) as usize;
+                for _ in 0..count {
+                    reader.read(item_type);
+                }
+            },
+        }
+    }
+}
+
+/// Returns per-vertex normals averaged from the faces sharing each vertex.
+fn smooth_normals(vertices: &[Vertex], faces: &[Vec<usize>]) -> Vec<Vector3> {
+    let mut normals = vec![Vector3::new(0.0, 0.0, 0.0); vertices.len()];
+
+    for face in faces {
+        for i in 1..face.len().saturating_sub(1) {
+            let (i0, i1, i2) = (face[0], face[i], face[i + 1]);
+            if i0 >= vertices.len() || i1 >= vertices.len() || i2 >= vertices.len() {
+                continue;
+            }
+
+            let p0 = vertices[i0].position;
+            let face_normal = Vector3::cross(vertices[i1].position - p0, vertices[i2].position - p0);
+            for &index in &[i0, i1, i2] {
+                normals[index] = normals[index] + face_normal;
+            }
+        }
+    }
+
+    normals.into_iter()
+        .map(|n| if Vector3::dot(n, n) > 0.0 { n.normalized() } else { n })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ascii_with_colors() {
+        let ply = "ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+        let (triangles, has_colors) = parse_ply(ply.as_bytes());
+
+        assert!(has_colors);
+        assert_eq!(triangles.len(), 2);
+        assert_eq!((triangles[1].v2.x, triangles[1].v2.y), (0.0, 1.0));
+        assert_eq!((triangles[0].vc1.x, triangles[0].vc1.y, triangles[0].vc1.z), (0.0, 1.0, 0.0));
+        assert_eq!((triangles[0].vn0.x, triangles[0].vn0.y, triangles[0].vn0.z), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn parses_binary_little_endian() {
+        let mut bytes = b"ply
+format binary_little_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+element face 1
+property list uchar uint vertex_indices
+end_header
+".to_vec();
+        for position in &[[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            for value in position.iter().chain([0.0f32, 0.0, 2.0].iter()) {
+                bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+        }
+        bytes.push(3);
+        for index in 0..3u32 {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let (triangles, has_colors) = parse_ply(&bytes);
+
+        assert!(!has_colors);
+        assert_eq!(triangles.len(), 1);
+        assert_eq!((triangles[0].v1.x, triangles[0].v1.y, triangles[0].v1.z), (1.0, 0.0, 0.0));
+        assert_eq!((triangles[0].vn2.x, triangles[0].vn2.y, triangles[0].vn2.z), (0.0, 0.0, 1.0));
+        assert_eq!((triangles[0].vc0.x, triangles[0].vc0.y, triangles[0].vc0.z), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn parses_binary_big_endian() {
+        let mut bytes = b"ply
+format binary_big_endian 1.0
+element vertex 3
+property double x
+property double y
+property double z
+element face 1
+property list uchar ushort vertex_indices
+end_header
+".to_vec();
+        for position in &[[0.0f64, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]] {
+            for value in position {
+                bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+            }
+        }
+        bytes.push(3);
+        for index in 0..3u16 {
+            bytes.extend_from_slice(&index.to_be_bytes());
+        }
+
+        let (triangles, _) = parse_ply(&bytes);
+
+        assert_eq!(triangles.len(), 1);
+        assert_eq!((triangles[0].v2.x, triangles[0].v2.y, triangles[0].v2.z), (0.0, 2.0, 0.0));
+    }
+}
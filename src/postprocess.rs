@@ -0,0 +1,264 @@
+//! Post Processing
+
+use std::f32::consts::PI;
+use framebuffer::FrameBuffer;
+
+/// Radius of the Lanczos kernel in destination pixels.
+const LANCZOS_RADIUS: f32 = 3.0;
+
+/// FXAA edges with less luma contrast than this fraction of the local maximum are skipped.
+const FXAA_EDGE_THRESHOLD: f32 = 0.125;
+
+/// FXAA edges with less luma contrast than this are skipped, avoiding work in dark areas.
+const FXAA_EDGE_THRESHOLD_MIN: f32 = 0.0312;
+
+/// Limits how much the FXAA search direction is reduced in flat areas.
+const FXAA_REDUCE_MIN: f32 = 1.0 / 128.0;
+
+/// Scales the FXAA search direction reduction by the local luma.
+const FXAA_REDUCE_MUL: f32 = 1.0 / 8.0;
+
+/// Farthest distance in pixels FXAA samples along an edge.
+const FXAA_SPAN_MAX: f32 = 8.0;
+
+/// Filter used to downsample a supersampled frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Downsample {
+    /// Averages each block of source pixels
+    Box,
+    /// Windowed sinc filter, sharper than box at a higher cost
+    Lanczos,
+}
+
+impl Downsample {
+    /// Returns the `Downsample` filter with the config name `name`.
+    pub fn from_name(name: &str) -> Option<Downsample> {
+        match name {
+            "box" => Some(Downsample::Box),
+            "lanczos" => Some(Downsample::Lanczos),
+            _ => None,
+        }
+    }
+
+    /// Returns the text representation of the filter.
+    pub fn name(&self) -> &str {
+        match *self {
+            Downsample::Box => "Box",
+            Downsample::Lanczos => "Lanczos",
+        }
+    }
+}
+
+/// Downsamples the resolved pixels of `source` into `destination`, which is `scale` times smaller.
+pub fn downsample(source: &FrameBuffer, destination: &mut FrameBuffer, scale: usize, filter: Downsample) {
+    if scale <= 1 {
+        destination.pixels_mut().copy_from_slice(source.pixels());
+        return;
+    }
+
+    match filter {
+        Downsample::Box => downsample_box(source, destination, scale),
+        Downsample::Lanczos => downsample_lanczos(source, destination, scale),
+    }
+}
+
+/// Averages each `scale` by `scale` block of `source` into one pixel of `destination`.
+fn downsample_box(source: &FrameBuffer, destination: &mut FrameBuffer, scale: usize) {
+    let (width, height) = (destination.width(), destination.height());
+    let source_pixels = source.pixels();
+    let source_width = source.width();
+    let block_size = (scale * scale) as u32;
+    let pixels = destination.pixels_mut();
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 3];
+            for sy in y * scale..(y + 1) * scale {
+                for sx in x * scale..(x + 1) * scale {
+                    let i = (sx + source_width * sy) * 3;
+                    sum[0] += u32::from(source_pixels[i]);
+                    sum[1] += u32::from(source_pixels[i + 1]);
+                    sum[2] += u32::from(source_pixels[i + 2]);
+                }
+            }
+
+            let i = (x + width * y) * 3;
+            pixels[i] = (sum[0] / block_size) as u8;
+            pixels[i + 1] = (sum[1] / block_size) as u8;
+            pixels[i + 2] = (sum[2] / block_size) as u8;
+        }
+    }
+}
+
+/// Downsamples `source` into `destination` with a separable Lanczos filter.
+fn downsample_lanczos(source: &FrameBuffer, destination: &mut FrameBuffer, scale: usize) {
+    let (width, height) = (destination.width(), destination.height());
+    let (source_width, source_height) = (source.width(), source.height());
+    let (weights, radius) = lanczos_weights(scale);
+
+    // Filter the rows into a buffer that is `scale` times narrower.
+    let source_pixels = source.pixels();
+    let mut horizontal = vec![0.0f32; width * source_height * 3];
+    for y in 0..source_height {
+        for x in 0..width {
+            let mut sum = [0.0f32; 3];
+            let mut total = 0.0;
+            for (tap, weight) in weights.iter().enumerate() {
+                let sx = (x * scale + tap) as isize - radius as isize;
+                if sx < 0 || sx >= source_width as isize {
+                    continue;
+                }
+
+                let i = (sx as usize + source_width * y) * 3;
+                for (channel, value) in sum.iter_mut().enumerate() {
+                    *value += f32::from(source_pixels[i + channel]) * weight;
+                }
+                total += weight;
+            }
+
+            let i = (x + width * y) * 3;
+            for (value, sum) in horizontal[i..i + 3].iter_mut().zip(sum.iter()) {
+                *value = sum / total;
+            }
+        }
+    }
+
+    // Filter the columns into the destination.
+    let pixels = destination.pixels_mut();
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0f32; 3];
+            let mut total = 0.0;
+            for (tap, weight) in weights.iter().enumerate() {
+                let sy = (y * scale + tap) as isize - radius as isize;
+                if sy < 0 || sy >= source_height as isize {
+                    continue;
+                }
+
+                let i = (x + width * sy as usize) * 3;
+                for (channel, value) in sum.iter_mut().enumerate() {
+                    *value += horizontal[i + channel] * weight;
+                }
+                total += weight;
+            }
+
+            let i = (x + width * y) * 3;
+            for (value, sum) in pixels[i..i + 3].iter_mut().zip(sum.iter()) {
+                *value = (sum / total).max(0.0).min(255.0).round() as u8;
+            }
+        }
+    }
+}
+
+/// Returns the Lanczos filter taps for downsampling by `scale` and the number of taps before a block.
+/// Tap `i` is applied to the source pixel `i - radius` pixels from the first pixel of a block.
+fn lanczos_weights(scale: usize) -> (Vec<f32>, usize) {
+    let radius = (LANCZOS_RADIUS * scale as f32).ceil() as usize;
+    // The center of a destination pixel lies halfway across its block of source pixels.
+    let center = scale as f32 * 0.5;
+
+    let weights = (0..radius * 2 + scale)
+        .map(|tap| {
+            let source_center = tap as f32 - radius as f32 + 0.5;
+            lanczos((source_center - center) / scale as f32)
+        })
+        .collect();
+
+    (weights, radius)
+}
+
+/// Returns the Lanczos kernel at `x`.
+fn lanczos(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else if x.abs() < LANCZOS_RADIUS {
+        let px = PI * x;
+        LANCZOS_RADIUS * px.sin() * (px / LANCZOS_RADIUS).sin() / (px * px)
+    } else {
+        0.0
+    }
+}
+
+/// Smooths aliased edges in the resolved pixels of `framebuffer` with FXAA.
+pub fn fxaa(framebuffer: &mut FrameBuffer) {
+    let (width, height) = (framebuffer.width(), framebuffer.height());
+    let source = framebuffer.pixels().iter().map(|&c| f32::from(c) / 255.0).collect::<Vec<_>>();
+    let luma = source.chunks(3).map(|c| c[0] * 0.299 + c[1] * 0.587 + c[2] * 0.114).collect::<Vec<_>>();
+
+    let luma_at = |x: isize, y: isize| {
+        let x = x.max(0).min(width as isize - 1) as usize;
+        let y = y.max(0).min(height as isize - 1) as usize;
+        luma[x + width * y]
+    };
+
+    let pixels = framebuffer.pixels_mut();
+
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let luma_m = luma_at(x, y);
+            let luma_nw = luma_at(x - 1, y - 1);
+            let luma_ne = luma_at(x + 1, y - 1);
+            let luma_sw = luma_at(x - 1, y + 1);
+            let luma_se = luma_at(x + 1, y + 1);
+
+            let luma_min = luma_m.min(luma_nw.min(luma_ne).min(luma_sw.min(luma_se)));
+            let luma_max = luma_m.max(luma_nw.max(luma_ne).max(luma_sw.max(luma_se)));
+            if luma_max - luma_min < FXAA_EDGE_THRESHOLD_MIN.max(luma_max * FXAA_EDGE_THRESHOLD) {
+                continue;
+            }
+
+            // Blur along the edge, perpendicular to the luma gradient.
+            let dir_x = -((luma_nw + luma_ne) - (luma_sw + luma_se));
+            let dir_y = (luma_nw + luma_sw) - (luma_ne + luma_se);
+            let dir_reduce = ((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL).max(FXAA_REDUCE_MIN);
+            let rcp_dir_min = 1.0 / (dir_x.abs().min(dir_y.abs()) + dir_reduce);
+            let dir_x = (dir_x * rcp_dir_min).max(-FXAA_SPAN_MAX).min(FXAA_SPAN_MAX);
+            let dir_y = (dir_y * rcp_dir_min).max(-FXAA_SPAN_MAX).min(FXAA_SPAN_MAX);
+
+            let center_x = x as f32 + 0.5;
+            let center_y = y as f32 + 0.5;
+            let sample = |t: f32| bilinear(&source, width, height, center_x + dir_x * t, center_y + dir_y * t);
+
+            let a1 = sample(1.0 / 3.0 - 0.5);
+            let a2 = sample(2.0 / 3.0 - 0.5);
+            let b1 = sample(-0.5);
+            let b2 = sample(0.5);
+
+            let color_a = [(a1[0] + a2[0]) * 0.5, (a1[1] + a2[1]) * 0.5, (a1[2] + a2[2]) * 0.5];
+            let color_b = [
+                color_a[0] * 0.5 + (b1[0] + b2[0]) * 0.25,
+                color_a[1] * 0.5 + (b1[1] + b2[1]) * 0.25,
+                color_a[2] * 0.5 + (b1[2] + b2[2]) * 0.25,
+            ];
+
+            // The wider blur is only used if it stays within the local luma range.
+            let luma_b = color_b[0] * 0.299 + color_b[1] * 0.587 + color_b[2] * 0.114;
+            let color = if luma_b < luma_min || luma_b > luma_max { color_a } else { color_b };
+
+            let i = (x as usize + width * y as usize) * 3;
+            for (value, channel) in pixels[i..i + 3].iter_mut().zip(color.iter()) {
+                *value = (channel * 255.0).max(0.0).min(255.0).round() as u8;
+            }
+        }
+    }
+}
+
+/// Returns the bilinearly filtered color of `pixels` at `x`, `y`, with pixel centers at half coordinates.
+fn bilinear(pixels: &[f32], width: usize, height: usize, x: f32, y: f32) -> [f32; 3] {
+    let x = (x - 0.5).max(0.0).min(width as f32 - 1.0);
+    let y = (y - 0.5).max(0.0).min(height as f32 - 1.0);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+    let mut color = [0.0; 3];
+    for (channel, value) in color.iter_mut().enumerate() {
+        let c00 = pixels[(x0 + width * y0) * 3 + channel];
+        let c10 = pixels[(x1 + width * y0) * 3 + channel];
+        let c01 = pixels[(x0 + width * y1) * 3 + channel];
+        let c11 = pixels[(x1 + width * y1) * 3 + channel];
+        *value = (c00 * (1.0 - tx) + c10 * tx) * (1.0 - ty) + (c01 * (1.0 - tx) + c11 * tx) * ty;
+    }
+
+    color
+}
@@ -3,143 +3,471 @@
 extern crate sdl2;
 extern crate tdmath;
 
-use self::tdmath::{Vector3, Vector2i, Matrix4};
+use self::tdmath::{Vector3, Vector4, Matrix4};
 use model::{Model, Triangle};
 use sdl2::pixels::Color;
-use sdl2::render::Canvas;
-use sdl2::rect::Point;
 use std::mem;
+use std::cmp;
+use std::cmp::Ordering;
 use zbuffer::ZBuffer;
-use image::{GenericImage, DynamicImage};
-use camera::{Camera, Projection};
-use std::path::{Path, PathBuf};
-use material::Material;
-use std::fs::File;
-use std::io::prelude::*;
-use toml::Value;
-use std::collections::HashMap;
+use framebuffer::FrameBuffer;
+use aov::{AovBuffers, AovFragment};
+use postprocess::{self, Downsample};
+use image::{self, DynamicImage, GenericImage};
+use camera::{Camera, CameraMode, CameraTransition, Projection, StandardView};
+use std::path::Path;
+use material::{Material, AlphaMode, CullMode, Winding};
+use scene::Scene;
+use scene_file::{SceneDescription, ModelDescription, CameraDescription};
+use light::Light;
+use bounds::BoundingSphere;
+use transform::NormalMatrix;
+use debug_view::{self, DebugView};
+use abuffer::{ABuffer, Fragment};
+use shading::{self, Shading};
+use toon::Toon;
+use ssao::{Ssao, SsaoBuffers};
+use fog::Fog;
+
+/// Largest supported MSAA sample count.
+pub const MAX_SAMPLES: usize = 8;
+
+/// Largest supported supersampling render scale.
+pub const MAX_RENDER_SCALE: usize = 4;
+
+/// Number of fractional bits in fixed point screen coordinates.
+const SUBPIXEL_BITS: i64 = 8;
+
+/// Number of fixed point steps per pixel.
+const SUBPIXEL_SCALE: f32 = (1 << SUBPIXEL_BITS) as f32;
+
+/// Triangles with a vertex further than this many pixels from the screen are not drawn.
+const MAX_SCREEN_COORDINATE: f32 = (1 << 20) as f32;
 
 /// Renderer
 pub struct Renderer {
-    light_dir: Vector3,
+    lights: Vec<Light>,
     models: Vec<Model>,
+    scene: Scene,
     zbuffer: ZBuffer,
+    framebuffer: FrameBuffer,
+    output: FrameBuffer,
+    render_scale: usize,
+    supersampling: bool,
+    downsample: Downsample,
+    fxaa: bool,
+    debug_view: DebugView,
+    shading: Shading,
+    toon: Toon,
+    matcap: Option<Box<DynamicImage>>,
+    ssao: Ssao,
+    ssao_buffers: Option<SsaoBuffers>,
+    fog: Fog,
+    fog_enabled: bool,
+    abuffer: Option<ABuffer>,
+    aovs: Option<AovBuffers>,
+    /// Depth, normal and id passes kept between frames for toon outlines when AOVs are not enabled
+    outline_aovs: Option<AovBuffers>,
     material: Material,
     camera: Camera,
-    rot_x: f32,
     ambient_intensity: f32,
     smooth_shading: bool,
+    background: Color,
+    selected: Option<usize>,
+    dolly_zoom: bool,
+    transition: Option<CameraTransition>,
+    transition_duration: f32,
+    width: usize,
+    height: usize,
 }
 
 impl Renderer {
     /// Returns a new `Renderer`.
     pub fn new(width: usize, height: usize) -> Renderer {
-        Renderer { light_dir: Vector3::new(0.0, 0.0, -1.0),
+        Renderer { lights: vec![Light::default()],
                    models: Vec::new(),
+                   scene: Scene::new(),
                    zbuffer: ZBuffer::new(width, height),
+                   framebuffer: FrameBuffer::new(width, height),
+                   output: FrameBuffer::new(width, height),
+                   render_scale: 2,
+                   supersampling: false,
+                   downsample: Downsample::Box,
+                   fxaa: false,
+                   debug_view: DebugView::Shaded,
+                   shading: Shading::Lit,
+                   toon: Toon::default(),
+                   matcap: None,
+                   ssao: Ssao::default(),
+                   ssao_buffers: None,
+                   fog: Fog::default(),
+                   fog_enabled: false,
+                   abuffer: None,
+                   aovs: None,
+                   outline_aovs: None,
                    material: Material::default(),
                    camera: Camera::default(),
-                   rot_x: 1.57,
-                   ambient_intensity: 0.0,
+                   ambient_intensity: 0.2,
                    smooth_shading: true,
+                   background: Color::RGB(65, 65, 65),
+                   selected: None,
+                   dolly_zoom: false,
+                   transition: None,
+                   transition_duration: 0.5,
+                   width,
+                   height,
         }
     }
 
-    /// Loads the models in `model_paths`.
+    /// Loads the models in `model_paths` and adds a root scene node for each.
     pub fn load_models(&mut self, model_paths: Vec<&Path>) {
         for path in model_paths {
-            let model = Model::new(path);
-            self.models.push(model);
+            self.load_model(path, None);
         }
     }
 
+    /// Loads the model at `model_path` into a new scene node under `parent`.
+    /// Returns the index of the scene node.
+    pub fn load_model(&mut self, model_path: &Path, parent: Option<usize>) -> usize {
+        let model = Model::new(model_path);
+        self.models.push(model);
+
+        let name = model_path.file_stem().and_then(|n| n.to_str()).unwrap_or("Model");
+        let node = self.scene.add_node(name, parent);
+        self.scene.node_mut(node).model = Some(self.models.len() - 1);
+        node
+    }
+
     /// Load the material at the `material_path`.
+    /// This material is used by models that do not have their own material.
     pub fn load_material(&mut self, material_path: &Path) {
-        let mut f = File::open(material_path).unwrap_or_else(|_| panic!("File not found: {:?}", material_path));
-        let mut file_contents = String::new();
-        f.read_to_string(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", material_path));
+        self.material = Material::load(material_path);
+    }
+
+    /// Loads the matcap image at `matcap_path` and switches to matcap shading.
+    pub fn load_matcap(&mut self, matcap_path: &Path) {
+        let matcap = image::open(matcap_path).unwrap_or_else(|_| panic!("Cannot load texture: {:?}", matcap_path));
+        self.matcap = Some(Box::new(matcap));
+        self.shading = Shading::Matcap;
+    }
+
+    /// Replaces the current scene with the scene file at `scene_path`.
+    pub fn load_scene(&mut self, scene_path: &Path) {
+        let description = SceneDescription::load(scene_path);
 
-        let toml = file_contents.parse::<Value>().unwrap_or_else(|_| panic!("Unable to parse material: {:?}", material_path));
+        self.models.clear();
+        self.scene = Scene::new();
+        self.selected = None;
 
-        let mut material_map = HashMap::new();
+        let mut nodes = Vec::new();
+        for model_description in &description.models {
+            let node_id = self.load_model(&model_description.path, None);
 
-        if let Some(albedo) = toml.get("albedo") {
-            if let Some(albedo_path) = albedo.as_str() {
-                let mut full_path = PathBuf::new();
-                full_path.push(material_path);
-                full_path.pop(); // Remove material name
-                full_path.push(albedo_path);
-                material_map.insert("albedo".to_owned(), full_path.to_str().unwrap().to_owned());
+            if let Some(ref material_path) = model_description.material {
+                self.models.last_mut().unwrap().set_material(Material::load(material_path));
             }
+
+            let node = self.scene.node_mut(node_id);
+            node.name = model_description.name.clone();
+            node.translation = model_description.translation;
+            node.rotation = model_description.rotation;
+            node.scale = model_description.scale;
+            nodes.push(node_id);
         }
 
-        if let Some(specular) = toml.get("specular") {
-            if let Some(specular_path) = specular.as_str() {
-                let mut full_path = PathBuf::new();
-                full_path.push(material_path);
-                full_path.pop(); // Remove material name
-                full_path.push(specular_path);
-                material_map.insert("specular".to_owned(), full_path.to_str().unwrap().to_owned());
+        // Parents are resolved after all nodes exist so they can be listed in any order.
+        for (model_description, &node_id) in description.models.iter().zip(nodes.iter()) {
+            if let Some(ref parent_name) = model_description.parent {
+                match self.scene.nodes().iter().position(|n| &n.name == parent_name) {
+                    Some(parent_id) => self.scene.set_parent(node_id, Some(parent_id)),
+                    None => panic!("Unknown parent in scene: {}", parent_name),
+                }
             }
         }
 
-        if let Some(normal) = toml.get("normal") {
-            if let Some(normal_path) = normal.as_str() {
-                let mut full_path = PathBuf::new();
-                full_path.push(material_path);
-                full_path.pop(); // Remove material name
-                full_path.push(normal_path);
-                material_map.insert("normal".to_owned(), full_path.to_str().unwrap().to_owned());
+        if let Some(ref material_path) = description.material {
+            self.material = Material::load(material_path);
+        }
+
+        self.lights = description.lights;
+        self.background = Color::RGB(description.background[0], description.background[1], description.background[2]);
+        self.set_camera_description(&description.camera);
+    }
+
+    /// Saves the current scene, camera and lights to a scene file at `scene_path`.
+    /// Nodes sharing a name are saved with a numbered suffix so that parents are restored to the right node.
+    pub fn save_scene(&self, scene_path: &Path) {
+        let mut names: Vec<String> = Vec::new();
+        for node in self.scene.nodes() {
+            let mut name = node.name.clone();
+            let mut suffix = 2;
+            while names.contains(&name) {
+                name = format!("{}_{}", node.name, suffix);
+                suffix += 1;
             }
+            names.push(name);
+        }
+
+        let models = self.scene.nodes().iter().enumerate().filter_map(|(node_id, node)| {
+            let model = &self.models[node.model?];
+
+            Some(ModelDescription {
+                name: names[node_id].clone(),
+                path: model.path().to_path_buf(),
+                material: model.material().and_then(|m| m.path()).map(|p| p.to_path_buf()),
+                parent: node.parent().map(|parent| names[parent].clone()),
+                translation: node.translation,
+                rotation: node.rotation,
+                scale: node.scale,
+            })
+        }).collect();
+
+        let description = SceneDescription {
+            models,
+            lights: self.lights.clone(),
+            camera: self.camera_description(),
+            background: [self.background.r, self.background.g, self.background.b],
+            material: self.material.path().map(|p| p.to_path_buf()),
+        };
+
+        description.save(scene_path);
+    }
+
+    /// Returns the camera's position, target, projection and clipping planes.
+    pub fn camera_description(&self) -> CameraDescription {
+        CameraDescription {
+            position: self.camera.position,
+            target: self.camera.target,
+            projection: self.camera.projection,
+            near: self.camera.near,
+            far: self.camera.far,
+        }
+    }
+
+    /// Moves the camera to the position, target, projection and clipping planes in `description`.
+    pub fn set_camera_description(&mut self, description: &CameraDescription) {
+        self.finish_transition();
+        self.camera.set_projection(description.projection);
+        self.camera.set_clip_planes(description.near, description.far);
+        self.camera.target = description.target;
+        self.camera.set_position(description.position);
+    }
+
+    /// Animates the camera to the position, target, projection and clipping planes in `description`.
+    pub fn restore_camera(&mut self, description: &CameraDescription) {
+        let from = self.view_camera();
+        self.set_camera_description(description);
+        self.start_transition(from);
+    }
+
+    /// Animates the camera to a standard orthographic view of its target.
+    pub fn set_standard_view(&mut self, view: StandardView) {
+        let from = self.view_camera();
+        self.camera.set_standard_view(view);
+        self.start_transition(from);
+    }
+
+    /// Starts animating from the `from` camera to the current camera.
+    fn start_transition(&mut self, from: Camera) {
+        self.transition = if self.transition_duration > 0.0 {
+            Some(CameraTransition::new(from, self.camera.clone(), self.transition_duration))
+        } else {
+            None
+        };
+    }
+
+    /// Jumps to the end of the current camera transition.
+    pub fn finish_transition(&mut self) {
+        self.transition = None;
+    }
+
+    /// Sets the duration of camera transitions in seconds. A duration of 0 disables transitions.
+    pub fn set_transition_duration(&mut self, duration: f32) {
+        self.transition_duration = duration.max(0.0);
+    }
+
+    /// Returns the camera used for rendering, which is animated during camera transitions.
+    fn view_camera(&self) -> Camera {
+        match self.transition {
+            Some(ref transition) => transition.camera(),
+            None => self.camera.clone(),
         }
+    }
+
+    /// Advances camera transitions by `delta_time` seconds.
+    pub fn update(&mut self, delta_time: f32) {
+        let finished = match self.transition {
+            Some(ref mut transition) => {
+                transition.update(delta_time);
+                transition.is_finished()
+            },
+            None => false,
+        };
 
-        self.material = Material::from_hashmap(material_map);
+        if finished {
+            self.transition = None;
+        }
     }
 
-    /// Render the scene to the canvas.
-    pub fn render(&mut self, canvas: &mut Canvas<sdl2::video::Window>) {
+    /// Render the scene to the frame buffer.
+    pub fn render(&mut self) {
+        // Toon outlines are found from the depth, normal and id passes, recorded into a scratch buffer if AOVs are not enabled.
+        let outline_aovs = self.shading == Shading::Toon && self.toon.outline_width > 0 && self.aovs.is_none();
+        if outline_aovs {
+            let (width, height) = (self.width, self.height);
+            self.aovs = Some(self.outline_aovs.take().unwrap_or_else(|| AovBuffers::new(width, height)));
+        }
+
         self.zbuffer.clear();
+        self.framebuffer.clear([self.background.r, self.background.g, self.background.b]);
+        if let Some(ref mut aovs) = self.aovs {
+            aovs.clear();
+        }
+        if let Some(ref mut abuffer) = self.abuffer {
+            abuffer.clear();
+        }
+        if let Some(ref mut ssao_buffers) = self.ssao_buffers {
+            ssao_buffers.clear(self.framebuffer.sample_count());
+        }
 
-        let (width, height) = canvas.viewport().size();
+        let (width, height) = (self.framebuffer.width(), self.framebuffer.height());
         let aspect = width as f32 / height as f32;
 
-        let projection = match self.camera.projection {
-            Projection::Orthographic(scale) => Matrix4::ortho(scale * aspect, -scale * aspect, -scale, scale, 0.1, 50.0),
-            Projection::Perspective(fov) => Matrix4::perpective(fov, -aspect, 0.1, 50.0)
+        let camera = self.view_camera();
+
+        let projection = match camera.projection {
+            Projection::Orthographic(scale) => Matrix4::ortho(scale * aspect, -scale * aspect, -scale, scale, camera.near, camera.far),
+            Projection::Perspective(fov) => Matrix4::perpective(fov, -aspect, camera.near, camera.far)
         };
 
-        let view = Matrix4::look_at(self.camera.position, Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let view = camera.view_matrix();
+        let counter_clockwise_area = counter_clockwise_area(projection, camera.near, camera.far);
 
-        for model in &self.models {
-            let model_matrix = Matrix4::translation(0.0, 0.0, 0.0) * Matrix4::scale(1.0, 1.0, 1.0);
+        let (lights, models, scene, default_material, toon, matcap) = (&self.lights, &self.models, &self.scene, &self.material, &self.toon, self.matcap.as_ref().map(|m| &**m));
+        let (ambient_intensity, smooth_shading, debug_view, shading, render_scale) = (self.ambient_intensity, self.smooth_shading, self.debug_view, self.shading, self.scale());
+        let fog = if self.fog_enabled { Some(&self.fog) } else { None };
+        let background = Vector3::new(f32::from(self.background.r), f32::from(self.background.g), f32::from(self.background.b));
+        let render_parameters = |node_id: usize, model_matrix: Matrix4, submesh_index: usize| {
+            let model = &models[scene.node(node_id).model.unwrap()];
+            let submesh = &model.submeshes()[submesh_index];
 
-            let render_params = RenderParameters {
+            RenderParameters {
                 model: model_matrix,
+                normal_matrix: NormalMatrix::new(model_matrix),
                 view,
                 projection,
-                light_dir: self.light_dir,
-                texture: &self.material.albedo,
-                ambient_intensity: self.ambient_intensity,
-                smooth_shading: self.smooth_shading,
+                lights,
+                camera_position: camera.position,
+                // A model's own material comes first, then a material loaded with `--material` or by the scene file,
+                // and only then the model's `.mtl` materials.
+                material: match model.material() {
+                    Some(material) => material,
+                    None if default_material.path().is_some() => default_material,
+                    None => model.submesh_material(submesh).unwrap_or(default_material),
+                },
+                ambient_intensity,
+                smooth_shading,
+                vertex_colors: model.has_vertex_colors(),
+                object_id: node_id as u32,
+                render_scale,
+                counter_clockwise_area,
+                debug_view,
+                shading,
+                toon,
+                matcap,
+                fog,
+                background,
+                near: camera.near,
+                far: camera.far,
+            }
+        };
+
+        let mut blended_triangles = Vec::new();
+        let mut targets = RenderTargets {
+            framebuffer: &mut self.framebuffer,
+            zbuffer: &mut self.zbuffer,
+            abuffer: &mut self.abuffer,
+            aovs: &mut self.aovs,
+            ssao: &mut self.ssao_buffers,
+        };
+
+        for (node_id, node) in scene.nodes().iter().enumerate() {
+            let model = match node.model {
+                Some(model_index) => &models[model_index],
+                None => continue,
             };
 
-            for triangle in model.triangles() {
-                let normal = Vector3::cross(triangle.v2.xyz() - triangle.v0.xyz(), triangle.v1.xyz() - triangle.v0.xyz()).normalized();
+            let model_matrix = scene.world_matrix(node_id);
+            let model_view = view * model_matrix;
 
-                let camera_forward = (self.camera.position).normalized();
-                if Vector3::dot(normal, camera_forward) > 0.0 {
-                    continue;
+            for (submesh_index, submesh) in model.submeshes().iter().enumerate() {
+                let render_params = render_parameters(node_id, model_matrix, submesh_index);
+
+                for (index, triangle) in model.submesh_triangles(submesh).iter().enumerate() {
+                    // Blended triangles are drawn after every opaque triangle.
+                    if render_params.material.alpha_mode == AlphaMode::Blend {
+                        let centroid = (triangle.v0.xyz() + triangle.v1.xyz() + triangle.v2.xyz()) * (1.0 / 3.0);
+                        let depth = -(model_view * Vector4::new(centroid.x, centroid.y, centroid.z, 1.0)).z;
+                        blended_triangles.push((depth, node_id, model_matrix, submesh_index, submesh.start + index));
+                        continue;
+                    }
+
+                    Renderer::draw_triangle(&mut targets, *triangle, (submesh.start + index) as u32, &render_params);
                 }
+            }
+        }
+
+        // Occlusion is applied before blending so transparent surfaces are not darkened by what is behind them.
+        if let Some(ref mut ssao_buffers) = *targets.ssao {
+            self.ssao.apply(targets.framebuffer, targets.zbuffer, ssao_buffers, projection, camera.near, camera.far);
+        }
+
+        // Without the A-buffer, blended triangles are drawn back to front so each one blends over everything behind it.
+        if targets.abuffer.is_none() {
+            blended_triangles.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        }
+        for &(_, node_id, model_matrix, submesh_index, triangle_index) in &blended_triangles {
+            let render_params = render_parameters(node_id, model_matrix, submesh_index);
+            let triangle = models[scene.node(node_id).model.unwrap()].triangles()[triangle_index];
+
+            Renderer::draw_triangle(&mut targets, triangle, triangle_index as u32, &render_params);
+        }
+
+        if let Some(ref abuffer) = self.abuffer {
+            abuffer.composite(&mut self.framebuffer);
+        }
 
-                Renderer::draw_triangle(canvas, &mut self.zbuffer, *triangle, &render_params);
+        if self.debug_view == DebugView::Overdraw {
+            for y in 0..self.framebuffer.height() {
+                for x in 0..self.framebuffer.width() {
+                    match self.framebuffer.overdraw(x, y) {
+                        0 => (),
+                        count => self.framebuffer.set(debug_view::overdraw_color(count), x, y),
+                    }
+                }
             }
         }
+
+        self.framebuffer.resolve();
+        postprocess::downsample(&self.framebuffer, &mut self.output, self.scale(), self.downsample);
+        if let (Shading::Toon, Some(aovs)) = (self.shading, self.aovs.as_ref()) {
+            self.toon.draw_outlines(&mut self.output, aovs);
+        }
+        if outline_aovs {
+            self.outline_aovs = self.aovs.take();
+        }
+        if self.fxaa {
+            postprocess::fxaa(&mut self.output);
+        }
     }
 
-    /// Draw a triangle to the canvas.
-    fn draw_triangle(canvas: &mut Canvas<sdl2::video::Window>, zbuffer: &mut ZBuffer, triangle: Triangle, render_params: &RenderParameters) {
-        let canvas_width = canvas.viewport().width() as f32;
-        let canvas_height = canvas.viewport().height() as f32;
+    /// Draw a triangle to the frame buffer.
+    fn draw_triangle(targets: &mut RenderTargets, triangle: Triangle, triangle_id: u32, render_params: &RenderParameters) {
+        let framebuffer = &mut *targets.framebuffer;
+        let zbuffer = &mut *targets.zbuffer;
+        let (abuffer, aovs, ssao) = (&mut *targets.abuffer, &mut *targets.aovs, &mut *targets.ssao);
+        let canvas_width = framebuffer.width() as f32;
+        let canvas_height = framebuffer.height() as f32;
 
         let v0mvp = render_params.projection * render_params.view * render_params.model * triangle.v0;
         let v1mvp = render_params.projection * render_params.view * render_params.model * triangle.v1;
@@ -149,99 +477,357 @@ impl Renderer {
         let ndc1 = Vector3::new(v1mvp.x / v1mvp.w, v1mvp.y / v1mvp.w, v1mvp.z / v1mvp.w);
         let ndc2 = Vector3::new(v2mvp.x / v2mvp.w, v2mvp.y / v2mvp.w, v2mvp.z / v2mvp.w);
 
-        let p0 = Vector2i::new(to_screen_space(ndc0.x, canvas_width as f32), to_screen_space(ndc0.y, canvas_height as f32));
-        let p1 = Vector2i::new(to_screen_space(ndc1.x, canvas_width as f32), to_screen_space(ndc1.y, canvas_height as f32));
-        let p2 = Vector2i::new(to_screen_space(ndc2.x, canvas_width as f32), to_screen_space(ndc2.y, canvas_height as f32));
-        
         let screen_space0 = Vector3::new((ndc0.x + 1.0) * canvas_width / 2.0, (ndc0.y + 1.0) * canvas_height / 2.0, ndc0.z);
         let screen_space1 = Vector3::new((ndc1.x + 1.0) * canvas_width / 2.0, (ndc1.y + 1.0) * canvas_height / 2.0, ndc1.z);
         let screen_space2 = Vector3::new((ndc2.x + 1.0) * canvas_width / 2.0, (ndc2.y + 1.0) * canvas_height / 2.0, ndc2.z);
 
-        let (bbox_min, bbox_max) = Vector2i::bbox3(p0, p1, p2);
+        // Vertices far outside the screen would overflow the fixed point edge functions.
+        let in_range = |p: Vector3| p.x.abs() < MAX_SCREEN_COORDINATE && p.y.abs() < MAX_SCREEN_COORDINATE;
+        if !in_range(screen_space0) || !in_range(screen_space1) || !in_range(screen_space2) {
+            return;
+        }
+
+        let p0 = FixedPoint::new(screen_space0);
+        let p1 = FixedPoint::new(screen_space1);
+        let p2 = FixedPoint::new(screen_space2);
+
+        // Twice the signed area of the triangle, used to orient the edges and normalize the barycentric weights.
+        let area = edge_function(p0, p1, p2);
+        if area == 0 {
+            return;
+        }
+        let front_facing = is_front_facing(area, render_params.counter_clockwise_area, render_params.material.winding);
+        // Back faces are drawn and highlighted in the back face debug view.
+        let cull_mode = match render_params.debug_view {
+            DebugView::BackFaces => CullMode::None,
+            _ => render_params.material.cull_mode,
+        };
+        if is_culled(front_facing, cull_mode) {
+            return;
+        }
+
+        let (edges, area) = oriented_edges(p0, p1, p2, area);
+
+        let min_x = cmp::max(cmp::min(p0.x, cmp::min(p1.x, p2.x)) >> SUBPIXEL_BITS, 0);
+        let min_y = cmp::max(cmp::min(p0.y, cmp::min(p1.y, p2.y)) >> SUBPIXEL_BITS, 0);
+        let max_x = cmp::min(cmp::max(p0.x, cmp::max(p1.x, p2.x)) >> SUBPIXEL_BITS, canvas_width as i64 - 1);
+        let max_y = cmp::min(cmp::max(p0.y, cmp::max(p1.y, p2.y)) >> SUBPIXEL_BITS, canvas_height as i64 - 1);
+        if min_x > max_x || min_y > max_y {
+            return;
+        }
+
+        // Normals are carried to the fragments in world space, where the lights are.
+        let world_normal0 = render_params.normal_matrix.transform(triangle.vn0);
+        let world_normal1 = render_params.normal_matrix.transform(triangle.vn1);
+        let world_normal2 = render_params.normal_matrix.transform(triangle.vn2);
+
+        let world0 = (render_params.model * triangle.v0).xyz();
+        let world1 = (render_params.model * triangle.v1).xyz();
+        let world2 = (render_params.model * triangle.v2).xyz();
+        let face_normal = Vector3::cross(world1 - world0, world2 - world0).normalized();
+
+        // Directions of increasing u and v across the triangle, for bending normals with a normal map.
+        let (tangent, bitangent) = {
+            let (edge1, edge2) = (world1 - world0, world2 - world0);
+            let (du1, dv1) = (triangle.vt1.x - triangle.vt0.x, triangle.vt1.y - triangle.vt0.y);
+            let (du2, dv2) = (triangle.vt2.x - triangle.vt0.x, triangle.vt2.y - triangle.vt0.y);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() > ::std::f32::EPSILON {
+                ((edge1 * dv2 - edge2 * dv1) / determinant, (edge2 * du1 - edge1 * du2) / determinant)
+            } else {
+                (Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0))
+            }
+        };
+
+        let model_view = render_params.view * render_params.model;
+        let view_z0 = (model_view * triangle.v0).z;
+        let view_z1 = (model_view * triangle.v1).z;
+        let view_z2 = (model_view * triangle.v2).z;
+
+        let sample_offsets = sample_offsets(zbuffer.sample_count())
+            .iter()
+            .map(|&(x, y)| ((x * SUBPIXEL_SCALE) as i64, (y * SUBPIXEL_SCALE) as i64))
+            .collect::<Vec<_>>();
 
-        for x in bbox_min.x..=bbox_max.x {
-            for y in bbox_min.y..=bbox_max.y {
-                if x >= canvas_width as i32 || y >= canvas_height as i32 || x < 0 || y < 0  {
+        // Edge function values at the center of the first pixel of each row, stepped incrementally across the bounding box.
+        let half_pixel = 1 << (SUBPIXEL_BITS - 1);
+        let first_center = ((min_x << SUBPIXEL_BITS) + half_pixel, (min_y << SUBPIXEL_BITS) + half_pixel);
+        let mut row = [edges[0].evaluate(first_center), edges[1].evaluate(first_center), edges[2].evaluate(first_center)];
+
+        for y in min_y..=max_y {
+            let mut weights = row;
+
+            for x in min_x..=max_x {
+                let center_weights = weights;
+                for (edge, weight) in edges.iter().zip(weights.iter_mut()) {
+                    *weight += edge.step_x;
+                }
+
+                // Coverage and depth are tested at every sample position in the pixel.
+                let mut covered = [false; MAX_SAMPLES];
+                let mut sample_depths = [0.0; MAX_SAMPLES];
+                let mut covered_weights = None;
+                for (sample, &(offset_x, offset_y)) in sample_offsets.iter().enumerate() {
+                    let sample_weights = [
+                        center_weights[0] + edges[0].offset(offset_x, offset_y),
+                        center_weights[1] + edges[1].offset(offset_x, offset_y),
+                        center_weights[2] + edges[2].offset(offset_x, offset_y),
+                    ];
+                    if !edges.iter().zip(sample_weights.iter()).all(|(edge, &weight)| edge.covers(weight)) {
+                        continue;
+                    }
+
+                    let uvw = barycentric(sample_weights, area);
+                    let z_distance = uvw.x * screen_space0.z + uvw.y * screen_space1.z + uvw.z * screen_space2.z;
+                    if z_distance.abs() <= 1.0 && z_distance < zbuffer.sample(x as usize, y as usize, sample) {
+                        covered[sample] = true;
+                        sample_depths[sample] = z_distance;
+                        covered_weights = covered_weights.or(Some(sample_weights));
+                    }
+                }
+
+                // The pixel is shaded once at its center, or at the first covered sample when the center is outside the triangle.
+                let uvw = match covered_weights {
+                    Some(_) if edges.iter().zip(center_weights.iter()).all(|(edge, &weight)| edge.covers(weight)) => barycentric(center_weights, area),
+                    Some(sample_weights) => barycentric(sample_weights, area),
+                    None => continue,
+                };
+                let (x, y) = (x as i32, y as i32);
+                framebuffer.add_overdraw(x as usize, y as usize);
+
+                let mut clip = Vector3::new(uvw.x / v0mvp.w, uvw.y / v1mvp.w, uvw.z / v2mvp.w);
+                clip = clip / (clip.x + clip.y + clip.z);
+
+                let u = clip.x * triangle.vt0.x + clip.y * triangle.vt1.x + clip.z * triangle.vt2.x;
+                let v = clip.x * triangle.vt0.y + clip.y * triangle.vt1.y + clip.z * triangle.vt2.y;
+
+                let normal = if render_params.smooth_shading {
+                    (world_normal0 * clip.x + world_normal1 * clip.y + world_normal2 * clip.z).normalized()
+                } else {
+                    face_normal
+                };
+                let normal = match render_params.material.normal_map {
+                    Some(ref normal_map) => perturb_normal(normal.normalized(), tangent, bitangent, texel(normal_map, u, v)),
+                    None => normal,
+                };
+
+                let view_normal = (render_params.view * Vector4::new(normal.x, normal.y, normal.z, 0.0)).xyz().normalized();
+
+                let depth = -(clip.x * view_z0 + clip.y * view_z1 + clip.z * view_z2);
+                let world_position = world0 * clip.x + world1 * clip.y + world2 * clip.z;
+
+                // Ambient light reaches every surface, and is the part of the lighting darkened by ambient occlusion.
+                let intensity = render_params.lights.iter()
+                    .map(|light| (-Vector3::dot(normal, light.direction)).max(0.0) * light.intensity)
+                    .sum::<f32>() + render_params.ambient_intensity;
+
+                let vertex_color = if render_params.vertex_colors {
+                    triangle.vc0 * clip.x + triangle.vc1 * clip.y + triangle.vc2 * clip.z
+                } else {
+                    Vector3::new(1.0, 1.0, 1.0)
+                };
+
+                let (base_color, texture_alpha) = match render_params.material.albedo {
+                    Some(ref texture) => {
+                        let color = texel(texture, u, v);
+                        (Vector3::new(color[0] * vertex_color.x, color[1] * vertex_color.y, color[2] * vertex_color.z) * 255.0, color[3])
+                    },
+                    None if render_params.vertex_colors => (vertex_color * 255.0, 1.0),
+                    None => (render_params.material.diffuse * 255.0, 1.0),
+                };
+
+                let material = render_params.material;
+                let alpha = texture_alpha * material.opacity;
+                if material.alpha_mode == AlphaMode::Mask && alpha < material.alpha_cutoff {
                     continue;
                 }
 
-                let barycentric = Vector3::barycentric(Vector3::new(x as f32, y as f32, 0.0), screen_space0, screen_space1, screen_space2);
-
-                if let Some(uvw) = barycentric {
-                    if uvw.x >= 0.0 && uvw.y >= 0.0 && uvw.z >= 0.0 {
-                        let z_distance = uvw.x * screen_space0.z + uvw.y * screen_space1.z + uvw.z * screen_space2.z;
-
-                        if z_distance.abs() <= 1.0 && z_distance < zbuffer.sample(x as usize, y as usize) {
-                            zbuffer.set(z_distance, x as usize, y as usize);
-
-                            let mut clip = Vector3::new(uvw.x / v0mvp.w, uvw.y / v1mvp.w, uvw.z / v2mvp.w);
-                            clip = clip / (clip.x + clip.y + clip.z);
-
-                            let u = clip.x * triangle.vt0.x + clip.y * triangle.vt1.x + clip.z * triangle.vt2.x;
-                            let v = clip.x * triangle.vt0.y + clip.y * triangle.vt1.y + clip.z * triangle.vt2.y;
-
-                            let normal = if render_params.smooth_shading {
-                                let n0 = clip.x * triangle.vn0.x + clip.y * triangle.vn1.x + clip.z * triangle.vn2.x;
-                                let n1 = clip.x * triangle.vn0.y + clip.y * triangle.vn1.y + clip.z * triangle.vn2.y;
-                                let n2 = clip.x * triangle.vn0.z + clip.y * triangle.vn1.z + clip.z * triangle.vn2.z;
-                                Vector3::new(n0, n1, n2)
-                            } else {
-                                Vector3::cross(triangle.v1.xyz() - triangle.v0.xyz(), triangle.v2.xyz() - triangle.v0.xyz()).normalized()
-                            };
-
-                            let intensity = -Vector3::dot(normal, render_params.light_dir);
-                            let intensity = if intensity >= 0.0 {
-                                intensity
-                            } else {
-                                render_params.ambient_intensity
-                            };
-
-                            let (red, green, blue) = match render_params.texture {
-                                Some(texture) => {
-                                    let width = texture.width() as f32;
-                                    let height = texture.height() as f32;
-
-                                    let x = clamp((u * width) as u32, 0, width as u32 - 1);
-                                    let y = clamp((v * height) as u32, 0, height as u32 - 1);
-
-                                    let color = texture.get_pixel(x, y);
-
-                                    ((f32::from(color.data[0]) * intensity) as u8, (f32::from(color.data[1]) * intensity) as u8, (f32::from(color.data[2]) * intensity) as u8)
-                                },
-                                None => ((128.0 * intensity) as u8, (128.0 * intensity) as u8, (128.0 * intensity) as u8)
-                            };
-                            
-                            canvas.set_draw_color(Color::RGB(red, green, blue));
-                            canvas.draw_point(Point::new(x, y)).unwrap();
+                // Blended surfaces are depth tested but do not hide what is drawn behind them later.
+                let blend = material.alpha_mode == AlphaMode::Blend;
+                if !blend {
+                    for sample in (0..sample_offsets.len()).filter(|&sample| covered[sample]) {
+                        zbuffer.set(sample_depths[sample], x as usize, y as usize, sample);
+                    }
+                }
+
+                let shaded = match (render_params.shading, render_params.matcap) {
+                    (Shading::Toon, _) => {
+                        let light = render_params.toon.shade(intensity);
+                        Vector3::new(base_color.x * light.x, base_color.y * light.y, base_color.z * light.z)
+                    },
+                    // The matcap holds the surface's color, tinted only by an albedo texture.
+                    (Shading::Matcap, Some(matcap)) => {
+                        let light = shading::sample_matcap(matcap, view_normal);
+                        match material.albedo {
+                            Some(_) => Vector3::new(base_color.x * light.x, base_color.y * light.y, base_color.z * light.z),
+                            None => light * 255.0,
                         }
+                    },
+                    _ => base_color * intensity + Renderer::specular(render_params, normal, world_position, u, v) * 255.0,
+                };
+
+                let color = match render_params.debug_view {
+                    DebugView::Shaded | DebugView::Overdraw => shaded,
+                    DebugView::Normals => (normal * 0.5 + Vector3::new(0.5, 0.5, 0.5)) * 255.0,
+                    DebugView::Uvs => Vector3::new(u - u.floor(), v - v.floor(), 0.0) * 255.0,
+                    DebugView::Depth => {
+                        let depth = 1.0 - clamp((depth - render_params.near) / (render_params.far - render_params.near), 0.0, 1.0);
+                        Vector3::new(depth, depth, depth) * 255.0
+                    },
+                    DebugView::TriangleColors => {
+                        let color = debug_view::triangle_color(render_params.object_id, triangle_id);
+                        Vector3::new(f32::from(color[0]), f32::from(color[1]), f32::from(color[2]))
+                    },
+                    DebugView::BackFaces if !front_facing => shaded * 0.5 + Vector3::new(127.5, 0.0, 0.0),
+                    DebugView::BackFaces => shaded,
+                };
+
+                // Fog fades shaded surfaces into the background with distance.
+                let fog = match (render_params.fog, render_params.debug_view) {
+                    (Some(fog), DebugView::Shaded) | (Some(fog), DebugView::BackFaces) => fog.amount(depth, world_position.y, render_params.far),
+                    _ => 0.0,
+                };
+                let color = color * (1.0 - fog) + render_params.background * fog;
+
+                let (red, green, blue) = (color.x as u8, color.y as u8, color.z as u8);
+
+                // The A-buffer keeps blended fragments to sort and composite once the frame is drawn.
+                if let (Some(abuffer), true) = (abuffer.as_mut(), blend) {
+                    let coverage = (0..sample_offsets.len())
+                        .filter(|&sample| covered[sample])
+                        .fold(0u8, |coverage, sample| coverage | (1 << sample));
+                    abuffer.insert(x as usize, y as usize, Fragment { color: [red, green, blue], alpha, depth, coverage });
+                    continue;
+                }
+
+                for sample in (0..sample_offsets.len()).filter(|&sample| covered[sample]) {
+                    let color = if blend {
+                        let behind = framebuffer.get_sample(x as usize, y as usize, sample);
+                        let mix = |front: u8, back: u8| (f32::from(front) * alpha + f32::from(back) * (1.0 - alpha)) as u8;
+                        [mix(red, behind[0]), mix(green, behind[1]), mix(blue, behind[2])]
+                    } else {
+                        [red, green, blue]
+                    };
+                    framebuffer.set_sample(color, x as usize, y as usize, sample);
+                }
+
+                // Only the ambient part of lit shading is occluded.
+                if let (Some(ssao), false) = (ssao.as_mut(), blend) {
+                    let ambient = match (render_params.shading, render_params.debug_view) {
+                        (Shading::Lit, DebugView::Shaded) | (Shading::Lit, DebugView::BackFaces) => base_color * (render_params.ambient_intensity * (1.0 - fog)),
+                        _ => Vector3::new(0.0, 0.0, 0.0),
+                    };
+                    ssao.set_normal(x as usize, y as usize, view_normal);
+                    for sample in (0..sample_offsets.len()).filter(|&sample| covered[sample]) {
+                        ssao.set_sample(x as usize, y as usize, sample, color, ambient);
                     }
                 }
+
+                // Supersampled frames record one AOV value per output pixel, of the nearest opaque surface.
+                let scale = render_params.render_scale as i32;
+                if let (Some(aovs), false, 0, 0) = (aovs.as_mut(), blend, x % scale, y % scale) {
+                    aovs.set((x / scale) as usize, (y / scale) as usize, AovFragment {
+                        depth,
+                        world_normal: normal,
+                        view_normal,
+                        uv: (u, v),
+                        object_id: render_params.object_id,
+                        triangle_id,
+                    });
+                }
+            }
+
+            for (edge, weight) in edges.iter().zip(row.iter_mut()) {
+                *weight += edge.step_y;
             }
         }
     }
 
-    /// Zoom by camera by `zoom_amount`.
+    /// Returns the Blinn-Phong specular highlight of the material at `world_position`, from 0 to 1 per channel.
+    fn specular(render_params: &RenderParameters, normal: Vector3, world_position: Vector3, u: f32, v: f32) -> Vector3 {
+        let material = render_params.material;
+        if material.shininess <= 0.0 {
+            return Vector3::new(0.0, 0.0, 0.0);
+        }
+
+        let to_camera = (render_params.camera_position - world_position).normalized();
+        let intensity = render_params.lights.iter()
+            .filter(|light| Vector3::dot(normal, light.direction) < 0.0)
+            .map(|light| {
+                let half = (to_camera - light.direction).normalized();
+                Vector3::dot(normal, half).max(0.0).powf(material.shininess) * light.intensity
+            })
+            .sum::<f32>();
+
+        let color = match material.specular_map {
+            Some(ref specular_map) => {
+                let scale = texel(specular_map, u, v);
+                Vector3::new(material.specular.x * scale[0], material.specular.y * scale[1], material.specular.z * scale[2])
+            },
+            None => material.specular,
+        };
+
+        color * intensity
+    }
+
+    /// Zoom the camera by `zoom_amount`.
+    /// With dolly zoom enabled a perspective camera moves towards the target instead of changing the FOV.
     pub fn zoom_camera(&mut self, zoom_amount: f32) {
+        self.finish_transition();
         match self.camera.projection {
-            Projection::Orthographic(scale) => {
-                self.camera.projection = Projection::Orthographic(scale + zoom_amount * 0.1)
-            },
-            Projection::Perspective(fov) => {
-                self.camera.projection = Projection::Perspective(fov + zoom_amount)
-            }
+            Projection::Perspective(_) if self.dolly_zoom => self.camera.dolly(zoom_amount * 0.025),
+            _ => self.camera.zoom(zoom_amount),
         }
     }
 
     /// Toggles the camera's projection mode.
     pub fn toggle_projection_mode(&mut self) {
-        self.camera.projection = match self.camera.projection {
-            Projection::Orthographic(_) => Projection::Perspective(60.0),
-            Projection::Perspective(_) => Projection::Orthographic(5.0),
+        let from = self.view_camera();
+        self.camera.toggle_projection();
+        self.start_transition(from);
+    }
+
+    /// Sets the camera's near and far clipping planes.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) {
+        self.camera.set_clip_planes(near, far);
+    }
+
+    /// Returns the camera's near and far clipping planes.
+    pub fn clip_planes(&self) -> (f32, f32) {
+        (self.camera.near, self.camera.far)
+    }
+
+    /// Sets the camera's perspective field of view in degrees.
+    pub fn set_fov(&mut self, fov: f32) {
+        self.camera.set_fov(fov);
+    }
+
+    /// Sets the camera's orthographic scale.
+    pub fn set_ortho_scale(&mut self, scale: f32) {
+        self.camera.set_ortho_scale(scale);
+    }
+
+    /// Sets whether zooming moves the camera instead of changing the FOV.
+    pub fn set_dolly_zoom(&mut self, dolly_zoom: bool) {
+        self.dolly_zoom = dolly_zoom;
+    }
+
+    /// Toggles between FOV zoom and dolly zoom.
+    pub fn toggle_dolly_zoom(&mut self) {
+        self.dolly_zoom = !self.dolly_zoom;
+    }
+
+    /// Returns the text representation of the current zoom mode.
+    pub fn zoom_mode_str(&self) -> &str {
+        if self.dolly_zoom {
+            "Dolly"
+        } else {
+            "FOV"
         }
     }
 
-    /// Draw a line on the canvas.
-    fn draw_line(canvas: &mut Canvas<sdl2::video::Window>, x0: i32, y0: i32, x1: i32, y1: i32) {
+    /// Draw a line on the frame buffer.
+    fn draw_line(framebuffer: &mut FrameBuffer, color: [u8; 3], x0: i32, y0: i32, x1: i32, y1: i32) {
         let mut steep = false;
 
         let mut x0 = x0;
@@ -269,9 +855,9 @@ impl Renderer {
 
         for x in x0..=x1 {
             if steep {
-                canvas.draw_point(Point::new(y, x)).unwrap();
+                framebuffer.set(color, y as usize, x as usize);
             } else {
-                canvas.draw_point(Point::new(x, y)).unwrap();
+                framebuffer.set(color, x as usize, y as usize);
             }
 
             error_2 += d_error_2;
@@ -287,15 +873,398 @@ impl Renderer {
         }
     }
 
-    /// Orbit the camera around the origin.
-    pub fn orbit(&mut self, delta_x: f32, _delta_y: f32) {
-        self.rot_x += delta_x;
-        self.camera.position = Vector3::new(10.0 * self.rot_x.cos(), 0.0, 10.0 * self.rot_x.sin());
+    /// Orbit the camera around its target.
+    /// `delta_x` rotates around the vertical axis and `delta_y` changes the camera's pitch.
+    pub fn orbit(&mut self, delta_x: f32, delta_y: f32) {
+        self.finish_transition();
+        self.camera.orbit(-delta_x, delta_y);
+    }
+
+    /// Sets the camera's orbit pitch above the target to `elevation` radians.
+    pub fn set_elevation(&mut self, elevation: f32) {
+        self.finish_transition();
+        let delta = elevation - self.camera.pitch;
+        self.camera.orbit(0.0, delta);
+    }
+
+    /// Pan the camera's target in screen space.
+    /// The deltas are scaled by the camera distance so panning speed matches the view.
+    pub fn pan(&mut self, delta_x: f32, delta_y: f32) {
+        self.finish_transition();
+        let distance = self.camera.distance;
+        self.camera.pan(-delta_x * distance, delta_y * distance);
+    }
+
+    /// Move the camera towards or away from its target.
+    pub fn dolly(&mut self, amount: f32) {
+        self.finish_transition();
+        self.camera.dolly(amount);
+    }
+
+    /// Turn the fly camera.
+    /// `delta_x` turns around the vertical axis and `delta_y` looks up or down.
+    pub fn look(&mut self, delta_x: f32, delta_y: f32) {
+        self.finish_transition();
+        self.camera.look(-delta_x, delta_y);
+    }
+
+    /// Move the fly camera by `right`, `up` and `forward` units.
+    pub fn fly(&mut self, right: f32, up: f32, forward: f32) {
+        if right == 0.0 && up == 0.0 && forward == 0.0 {
+            return;
+        }
+
+        self.finish_transition();
+        self.camera.fly(right, up, forward);
+    }
+
+    /// Toggles between the orbit and fly camera.
+    pub fn toggle_camera_mode(&mut self) {
+        self.finish_transition();
+        let mode = match self.camera.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+        self.camera.set_mode(mode);
+    }
+
+    /// Returns true if the fly camera is active.
+    pub fn is_fly_camera(&self) -> bool {
+        self.camera.mode == CameraMode::Fly
+    }
+
+    /// Returns the text representation of the current camera mode.
+    pub fn camera_mode_str(&self) -> &str {
+        match self.camera.mode {
+            CameraMode::Orbit => "Orbit",
+            CameraMode::Fly => "Fly",
+        }
     }
 
     /// Resize the render window. Call this to resize the `ZBuffer`.
     pub fn resize(&mut self, width: usize, height: usize) {
-        self.zbuffer.resize(width, height);
+        self.width = width;
+        self.height = height;
+        self.output.resize(width, height);
+        if let Some(ref mut aovs) = self.aovs {
+            aovs.resize(width, height);
+        }
+        self.resize_render_targets();
+    }
+
+    /// Resizes the `ZBuffer` and the frame buffer drawn into to the supersampled resolution.
+    fn resize_render_targets(&mut self) {
+        let scale = self.scale();
+        self.zbuffer.resize(self.width * scale, self.height * scale);
+        self.framebuffer.resize(self.width * scale, self.height * scale);
+        if let Some(ref mut abuffer) = self.abuffer {
+            abuffer.resize(self.width * scale, self.height * scale);
+        }
+        if let Some(ref mut aovs) = self.outline_aovs {
+            aovs.resize(self.width, self.height);
+        }
+        if let Some(ref mut ssao_buffers) = self.ssao_buffers {
+            ssao_buffers.resize(self.width * scale, self.height * scale);
+        }
+    }
+
+    /// Returns the factor the frame is currently rendered larger than the output by.
+    fn scale(&self) -> usize {
+        if self.supersampling {
+            self.render_scale
+        } else {
+            1
+        }
+    }
+
+    /// Sets the supersampling render scale, 1 disables supersampling.
+    pub fn set_render_scale(&mut self, scale: usize) {
+        self.supersampling = scale > 1;
+        if self.supersampling {
+            self.render_scale = scale.min(MAX_RENDER_SCALE);
+        }
+        self.resize_render_targets();
+    }
+
+    /// Toggles supersampling at the last render scale set.
+    pub fn toggle_supersampling(&mut self) {
+        self.supersampling = !self.supersampling;
+        self.resize_render_targets();
+    }
+
+    /// Sets the filter used to downsample supersampled frames.
+    pub fn set_downsample(&mut self, downsample: Downsample) {
+        self.downsample = downsample;
+    }
+
+    /// Returns the text representation of the supersampling option.
+    pub fn supersampling_str(&self) -> String {
+        if self.supersampling {
+            format!("{}x {}", self.render_scale, self.downsample.name())
+        } else {
+            "Off".to_owned()
+        }
+    }
+
+    /// Sets whether FXAA is applied to the final frame.
+    pub fn set_fxaa(&mut self, fxaa: bool) {
+        self.fxaa = fxaa;
+    }
+
+    /// Toggles FXAA.
+    pub fn toggle_fxaa(&mut self) {
+        self.fxaa = !self.fxaa;
+    }
+
+    /// Sets what is drawn in place of the shaded color.
+    pub fn set_debug_view(&mut self, debug_view: DebugView) {
+        self.debug_view = debug_view;
+    }
+
+    /// Switches to the next debug view.
+    pub fn cycle_debug_view(&mut self) {
+        self.debug_view = self.debug_view.next();
+    }
+
+    /// Returns the text representation of the current debug view.
+    pub fn debug_view_str(&self) -> &str {
+        self.debug_view.name()
+    }
+
+    /// Sets how surfaces are lit.
+    pub fn set_shading(&mut self, shading: Shading) {
+        self.shading = shading;
+    }
+
+    /// Switches to the next shading mode, skipping matcap shading if no matcap is loaded.
+    pub fn cycle_shading(&mut self) {
+        self.shading = self.shading.next();
+        if self.shading == Shading::Matcap && self.matcap.is_none() {
+            self.shading = self.shading.next();
+        }
+    }
+
+    /// Returns the text representation of the current shading mode.
+    pub fn shading_str(&self) -> &str {
+        self.shading.name()
+    }
+
+    /// Sets the bands, ramp and outlines used by toon shading.
+    pub fn set_toon(&mut self, toon: Toon) {
+        self.toon = toon;
+    }
+
+    /// Returns the toon outline thickness in pixels.
+    pub fn outline_width(&self) -> u32 {
+        self.toon.outline_width
+    }
+
+    /// Sets the toon outline thickness in pixels.
+    pub fn set_outline_width(&mut self, width: u32) {
+        self.toon.outline_width = width;
+    }
+
+    /// Sets the radius, sample count and blur of the ambient occlusion.
+    pub fn set_ssao(&mut self, ssao: Ssao) {
+        self.ssao = ssao;
+    }
+
+    /// Sets whether ambient light is darkened by screen space ambient occlusion.
+    pub fn set_ssao_enabled(&mut self, enabled: bool) {
+        let scale = self.scale();
+        self.ssao_buffers = if enabled {
+            Some(SsaoBuffers::new(self.width * scale, self.height * scale))
+        } else {
+            None
+        };
+    }
+
+    /// Toggles screen space ambient occlusion.
+    pub fn toggle_ssao(&mut self) {
+        let enabled = self.ssao_buffers.is_none();
+        self.set_ssao_enabled(enabled);
+    }
+
+    /// Returns the text representation of the ambient occlusion option.
+    pub fn ssao_str(&self) -> &str {
+        match self.ssao_buffers {
+            Some(_) => "Enabled",
+            None => "Disabled",
+        }
+    }
+
+    /// Sets the mode, density and distances of the fog.
+    pub fn set_fog(&mut self, fog: Fog) {
+        self.fog = fog;
+    }
+
+    /// Sets whether distant surfaces fade into the background.
+    pub fn set_fog_enabled(&mut self, enabled: bool) {
+        self.fog_enabled = enabled;
+    }
+
+    /// Toggles fog.
+    pub fn toggle_fog(&mut self) {
+        self.fog_enabled = !self.fog_enabled;
+    }
+
+    /// Returns the text representation of the fog option.
+    pub fn fog_str(&self) -> &str {
+        if self.fog_enabled {
+            self.fog.mode.name()
+        } else {
+            "Off"
+        }
+    }
+
+    /// Sets whether blended materials are composited with the A-buffer instead of sorted by triangle.
+    pub fn set_order_independent_transparency(&mut self, enabled: bool) {
+        let scale = self.scale();
+        self.abuffer = if enabled {
+            Some(ABuffer::new(self.width * scale, self.height * scale))
+        } else {
+            None
+        };
+    }
+
+    /// Toggles between sorted blending and the A-buffer.
+    pub fn toggle_order_independent_transparency(&mut self) {
+        let enabled = self.abuffer.is_none();
+        self.set_order_independent_transparency(enabled);
+    }
+
+    /// Returns the text representation of the transparency mode.
+    pub fn transparency_str(&self) -> &str {
+        match self.abuffer {
+            Some(_) => "A-Buffer",
+            None => "Sorted",
+        }
+    }
+
+    /// Returns the text representation of the FXAA option.
+    pub fn fxaa_str(&self) -> &str {
+        if self.fxaa {
+            "Enabled"
+        } else {
+            "Disabled"
+        }
+    }
+
+    /// Sets the number of MSAA samples per pixel to 1, 2, 4 or 8.
+    pub fn set_msaa_samples(&mut self, samples: usize) {
+        let samples = match samples {
+            2 | 4 | MAX_SAMPLES => samples,
+            _ => 1,
+        };
+        self.zbuffer.set_sample_count(samples);
+        self.framebuffer.set_sample_count(samples);
+    }
+
+    /// Cycles the MSAA sample count through off, 2x, 4x and 8x.
+    pub fn cycle_msaa(&mut self) {
+        let samples = self.zbuffer.sample_count();
+        self.set_msaa_samples(if samples >= MAX_SAMPLES { 1 } else { samples * 2 });
+    }
+
+    /// Returns the text representation of the MSAA sample count.
+    pub fn msaa_str(&self) -> String {
+        match self.zbuffer.sample_count() {
+            1 => "Off".to_owned(),
+            samples => format!("{}x", samples),
+        }
+    }
+
+    /// Returns the render width and height in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Starts recording the depth, normal, UV and id passes while rendering.
+    pub fn enable_aovs(&mut self) {
+        self.aovs = Some(AovBuffers::new(self.width, self.height));
+    }
+
+    /// Returns the passes recorded by the last render if enabled.
+    pub fn aovs(&self) -> Option<&AovBuffers> {
+        self.aovs.as_ref()
+    }
+
+    /// Returns the frame buffer holding the last rendered frame at the output resolution.
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.output
+    }
+
+    /// Returns the world space bounding sphere of the node `node_id` and its children.
+    /// Returns None if none of the nodes have a model.
+    fn node_bounding_sphere(&self, node_id: usize) -> Option<BoundingSphere> {
+        let node = self.scene.node(node_id);
+        let own = node.model
+            .and_then(|model| self.models[model].bounding_sphere())
+            .map(|sphere| sphere.transformed(self.scene.world_matrix(node_id)));
+
+        node.children().iter()
+            .filter_map(|&child| self.node_bounding_sphere(child))
+            .fold(own, |bounds, child| match bounds {
+                Some(bounds) => Some(bounds.union(&child)),
+                None => Some(child),
+            })
+    }
+
+    /// Moves the camera to fit every model in the view.
+    pub fn frame_all(&mut self) {
+        let bounds = (0..self.scene.nodes().len())
+            .filter(|&id| self.scene.node(id).parent().is_none())
+            .filter_map(|id| self.node_bounding_sphere(id))
+            .fold(None, |bounds: Option<BoundingSphere>, sphere| match bounds {
+                Some(bounds) => Some(bounds.union(&sphere)),
+                None => Some(sphere),
+            });
+
+        if let Some(sphere) = bounds {
+            let aspect = self.width as f32 / self.height as f32;
+            let from = self.view_camera();
+            self.camera.frame(sphere.center, sphere.radius, aspect);
+            self.start_transition(from);
+        }
+    }
+
+    /// Moves the camera to fit the selected node and its children in the view.
+    /// Frames every model if nothing is selected.
+    pub fn frame_selected(&mut self) {
+        let bounds = self.selected.and_then(|id| self.node_bounding_sphere(id));
+
+        match bounds {
+            Some(sphere) => {
+                let aspect = self.width as f32 / self.height as f32;
+                let from = self.view_camera();
+                self.camera.frame(sphere.center, sphere.radius, aspect);
+                self.start_transition(from);
+            },
+            None => self.frame_all(),
+        }
+    }
+
+    /// Selects the next scene node, or clears the selection after the last node.
+    pub fn select_next(&mut self) {
+        self.selected = match self.selected {
+            Some(id) if id + 1 < self.scene.nodes().len() => Some(id + 1),
+            Some(_) => None,
+            None if !self.scene.nodes().is_empty() => Some(0),
+            None => None,
+        };
+    }
+
+    /// Returns the name of the selected scene node.
+    pub fn selected_str(&self) -> &str {
+        match self.selected {
+            Some(id) => &self.scene.node(id).name,
+            None => "None",
+        }
+    }
+
+    /// Sets the background color, which is also the color of the fog.
+    pub fn set_background(&mut self, background: Color) {
+        self.background = background;
     }
 
     /// Increase the scene's ambient intensity.
@@ -326,9 +1295,178 @@ impl Renderer {
     }
 }
 
-/// Coverts a scalar value to the screen space position.
-fn to_screen_space(num: f32, dimension: f32) -> i32 {
-    ((num + 1.0) * dimension / 2.0) as i32
+/// Returns the color of `texture` at `u`, `v` with each channel from 0 to 1.
+fn texel(texture: &DynamicImage, u: f32, v: f32) -> [f32; 4] {
+    let (width, height) = (texture.width(), texture.height());
+    let x = clamp((u * width as f32) as u32, 0, width - 1);
+    let y = clamp((v * height as f32) as u32, 0, height - 1);
+    let color = texture.get_pixel(x, y);
+
+    [
+        f32::from(color.data[0]) / 255.0,
+        f32::from(color.data[1]) / 255.0,
+        f32::from(color.data[2]) / 255.0,
+        f32::from(color.data[3]) / 255.0,
+    ]
+}
+
+/// Returns `normal` bent by the tangent space normal map `texel`.
+/// The normal is unchanged where the triangle has no texture coordinates to orient the map with.
+fn perturb_normal(normal: Vector3, tangent: Vector3, bitangent: Vector3, texel: [f32; 4]) -> Vector3 {
+    let tangent = tangent - normal * Vector3::dot(normal, tangent);
+    if Vector3::dot(tangent, tangent) <= ::std::f32::EPSILON {
+        return normal;
+    }
+    let tangent = tangent.normalized();
+
+    // Texture coordinates run down the image while the map's green channel points up it.
+    let up = Vector3::cross(normal, tangent);
+    let up = if Vector3::dot(up, bitangent) > 0.0 { up * -1.0 } else { up };
+
+    let (x, y, z) = (texel[0] * 2.0 - 1.0, texel[1] * 2.0 - 1.0, texel[2] * 2.0 - 1.0);
+    (tangent * x + up * y + normal * z).normalized()
+}
+
+/// Returns the sample positions within a pixel for `sample_count` samples, as offsets from the pixel center.
+/// The patterns are the standard D3D multisample positions.
+fn sample_offsets(sample_count: usize) -> &'static [(f32, f32)] {
+    static SAMPLES_1: [(f32, f32); 1] = [(0.0, 0.0)];
+    static SAMPLES_2: [(f32, f32); 2] = [(0.25, 0.25), (-0.25, -0.25)];
+    static SAMPLES_4: [(f32, f32); 4] = [(-0.125, -0.375), (0.375, -0.125), (-0.375, 0.125), (0.125, 0.375)];
+    static SAMPLES_8: [(f32, f32); 8] = [
+        (0.0625, -0.1875), (-0.0625, 0.1875), (0.3125, 0.0625), (-0.1875, -0.3125),
+        (-0.3125, 0.3125), (-0.4375, -0.0625), (0.1875, 0.4375), (0.4375, -0.4375),
+    ];
+
+    match sample_count {
+        2 => &SAMPLES_2,
+        4 => &SAMPLES_4,
+        8 => &SAMPLES_8,
+        _ => &SAMPLES_1,
+    }
+}
+
+/// Returns the sign of the screen space area of a triangle that is counter-clockwise in view space.
+/// The projection and screen mapping may mirror the image, so this is measured rather than assumed.
+fn counter_clockwise_area(projection: Matrix4, near: f32, far: f32) -> i64 {
+    let depth = (near + far) * 0.5;
+    let to_ndc = |x: f32, y: f32| {
+        let clip = projection * Vector4::new(x, y, -depth, 1.0);
+        (clip.x / clip.w, clip.y / clip.w)
+    };
+
+    let (a, b, c) = (to_ndc(0.0, 0.0), to_ndc(depth, 0.0), to_ndc(0.0, depth));
+    if (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0) > 0.0 {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Screen space position in fixed point subpixel units.
+#[derive(Debug, Copy, Clone)]
+struct FixedPoint {
+    x: i64,
+    y: i64,
+}
+
+impl FixedPoint {
+    /// Returns the screen space `position` snapped to the subpixel grid.
+    fn new(position: Vector3) -> FixedPoint {
+        FixedPoint {
+            x: (position.x * SUBPIXEL_SCALE).round() as i64,
+            y: (position.y * SUBPIXEL_SCALE).round() as i64,
+        }
+    }
+}
+
+/// Returns twice the signed area of the triangle `a`, `b`, `p`.
+fn edge_function(a: FixedPoint, b: FixedPoint, p: FixedPoint) -> i64 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Edge function of a triangle edge, positive on the inside of the triangle.
+struct Edge {
+    a: i64,
+    b: i64,
+    c: i64,
+    /// Change in value when moving one pixel right
+    step_x: i64,
+    /// Change in value when moving one pixel down
+    step_y: i64,
+    /// Subtracted from points exactly on the edge unless it is a top or left edge
+    bias: i64,
+}
+
+impl Edge {
+    /// Returns the edge from `from` to `to`.
+    fn new(from: FixedPoint, to: FixedPoint) -> Edge {
+        let a = from.y - to.y;
+        let b = to.x - from.x;
+        let c = -(a * from.x + b * from.y);
+
+        // Top-left fill rule: points exactly on an edge shared by two triangles belong to only one of them.
+        let top_left = a > 0 || (a == 0 && b < 0);
+
+        Edge {
+            a,
+            b,
+            c,
+            step_x: a << SUBPIXEL_BITS,
+            step_y: b << SUBPIXEL_BITS,
+            bias: if top_left { 0 } else { -1 },
+        }
+    }
+
+    /// Returns the value of the edge function at the subpixel position `p`.
+    fn evaluate(&self, p: (i64, i64)) -> i64 {
+        self.a * p.0 + self.b * p.1 + self.c
+    }
+
+    /// Returns the change in value when moving by a subpixel offset.
+    fn offset(&self, offset_x: i64, offset_y: i64) -> i64 {
+        self.a * offset_x + self.b * offset_y
+    }
+
+    /// Returns true if a point with the edge function value `weight` is inside this edge.
+    fn covers(&self, weight: i64) -> bool {
+        weight + self.bias >= 0
+    }
+}
+
+/// Returns true if a triangle with twice the signed screen space area `area` faces the camera.
+/// `counter_clockwise_area` has the sign of the area of counter-clockwise triangles.
+fn is_front_facing(area: i64, counter_clockwise_area: i64, winding: Winding) -> bool {
+    let counter_clockwise = area.signum() == counter_clockwise_area.signum();
+    match winding {
+        Winding::CounterClockwise => counter_clockwise,
+        Winding::Clockwise => !counter_clockwise,
+    }
+}
+
+/// Returns true if a triangle facing the camera or not, as given by `front_facing`, is removed by `cull_mode`.
+fn is_culled(front_facing: bool, cull_mode: CullMode) -> bool {
+    match cull_mode {
+        CullMode::Back => !front_facing,
+        CullMode::Front => front_facing,
+        CullMode::None => false,
+    }
+}
+
+/// Returns the edges of the triangle `p0`, `p1`, `p2` with twice the signed area `area`, oriented to be positive inside it,
+/// along with twice the unsigned area.
+fn oriented_edges(p0: FixedPoint, p1: FixedPoint, p2: FixedPoint, area: i64) -> ([Edge; 3], i64) {
+    if area > 0 {
+        ([Edge::new(p1, p2), Edge::new(p2, p0), Edge::new(p0, p1)], area)
+    } else {
+        ([Edge::new(p2, p1), Edge::new(p0, p2), Edge::new(p1, p0)], -area)
+    }
+}
+
+/// Returns the barycentric coordinates from the edge function values `weights` of a triangle with twice the area `area`.
+fn barycentric(weights: [i64; 3], area: i64) -> Vector3 {
+    let area = area as f32;
+    Vector3::new(weights[0] as f32 / area, weights[1] as f32 / area, weights[2] as f32 / area)
 }
 
 /// Clamps `val` between `min` and `max`.
@@ -344,13 +1482,126 @@ where T: PartialOrd
     }
 }
 
+/// Buffers written by the triangle renderer.
+struct RenderTargets<'a> {
+    framebuffer: &'a mut FrameBuffer,
+    zbuffer: &'a mut ZBuffer,
+    abuffer: &'a mut Option<ABuffer>,
+    aovs: &'a mut Option<AovBuffers>,
+    ssao: &'a mut Option<SsaoBuffers>,
+}
+
 /// Parameters to pass into the triangle renderer.
 pub struct RenderParameters<'a> {
     pub model: Matrix4,
+    pub normal_matrix: NormalMatrix,
     pub view: Matrix4,
     pub projection: Matrix4,
-    pub light_dir: Vector3,
-    pub texture: &'a Option<Box<DynamicImage>>,
+    pub lights: &'a [Light],
+    pub camera_position: Vector3,
+    pub material: &'a Material,
     pub ambient_intensity: f32,
     pub smooth_shading: bool,
-}
\ No newline at end of file
+    pub vertex_colors: bool,
+    pub object_id: u32,
+    pub render_scale: usize,
+    pub counter_clockwise_area: i64,
+    pub debug_view: DebugView,
+    pub shading: Shading,
+    pub toon: &'a Toon,
+    pub matcap: Option<&'a DynamicImage>,
+    pub fog: Option<&'a Fog>,
+    pub background: Vector3,
+    pub near: f32,
+    pub far: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use transform::{rotation_matrix, quaternion_from_axis_angle};
+
+    /// Returns how many of `triangles` cover the center of each pixel in a `size` by `size` grid.
+    fn coverage(triangles: &[[(f32, f32); 3]], size: i64) -> Vec<u32> {
+        let mut counts = vec![0; (size * size) as usize];
+
+        for triangle in triangles {
+            let point = |i: usize| FixedPoint::new(Vector3::new(triangle[i].0, triangle[i].1, 0.0));
+            let (p0, p1, p2) = (point(0), point(1), point(2));
+            let (edges, _) = oriented_edges(p0, p1, p2, edge_function(p0, p1, p2));
+
+            for y in 0..size {
+                for x in 0..size {
+                    let half_pixel = 1 << (SUBPIXEL_BITS - 1);
+                    let center = ((x << SUBPIXEL_BITS) + half_pixel, (y << SUBPIXEL_BITS) + half_pixel);
+                    if edges.iter().all(|edge| edge.covers(edge.evaluate(center))) {
+                        counts[(y * size + x) as usize] += 1;
+                    }
+                }
+            }
+        }
+
+        counts
+    }
+
+    #[test]
+    fn shared_edge_covers_each_pixel_once() {
+        // The pixel centers on the diagonal lie exactly on the shared edge.
+        let counts = coverage(&[[(0.0, 0.0), (8.0, 0.0), (8.0, 8.0)], [(0.0, 0.0), (8.0, 8.0), (0.0, 8.0)]], 8);
+        assert!(counts.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn edges_through_pixel_centers_cover_each_pixel_once() {
+        // Every edge of the 6 by 6 pixel square runs through a row or column of pixel centers,
+        // only one of each pair of opposite edges keeps them.
+        let counts = coverage(&[[(0.5, 0.5), (6.5, 0.5), (6.5, 6.5)], [(6.5, 6.5), (0.5, 6.5), (0.5, 0.5)]], 8);
+        assert!(counts.iter().all(|&count| count <= 1));
+        assert_eq!(counts.iter().sum::<u32>(), 36);
+    }
+
+    #[test]
+    fn back_faces_are_culled_for_both_windings() {
+        let point = |x: f32, y: f32| FixedPoint::new(Vector3::new(x, y, 0.0));
+        let counter_clockwise = edge_function(point(0.0, 0.0), point(4.0, 0.0), point(0.0, 4.0));
+        let clockwise = edge_function(point(0.0, 0.0), point(0.0, 4.0), point(4.0, 0.0));
+
+        // The screen mapping may mirror the image and flip the sign of counter-clockwise areas.
+        for &counter_clockwise_area in &[counter_clockwise.signum(), clockwise.signum()] {
+            let (front, back) = if counter_clockwise_area == counter_clockwise.signum() {
+                (counter_clockwise, clockwise)
+            } else {
+                (clockwise, counter_clockwise)
+            };
+
+            let culled = |area, winding, cull_mode| is_culled(is_front_facing(area, counter_clockwise_area, winding), cull_mode);
+
+            assert!(!culled(front, Winding::CounterClockwise, CullMode::Back));
+            assert!(culled(back, Winding::CounterClockwise, CullMode::Back));
+            assert!(!culled(back, Winding::Clockwise, CullMode::Back));
+            assert!(culled(front, Winding::Clockwise, CullMode::Back));
+
+            assert!(culled(front, Winding::CounterClockwise, CullMode::Front));
+            assert!(!culled(back, Winding::CounterClockwise, CullMode::Front));
+            assert!(!culled(front, Winding::CounterClockwise, CullMode::None));
+            assert!(!culled(back, Winding::Clockwise, CullMode::None));
+        }
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let model = Matrix4::translation(1.0, 2.0, 3.0)
+            * rotation_matrix(quaternion_from_axis_angle(Vector3::new(1.0, 1.0, 0.0), 0.7))
+            * Matrix4::scale(3.0, 0.5, 2.0);
+        let normal_matrix = NormalMatrix::new(model);
+
+        let (v0, v1, v2) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 2.0));
+        let world = |v: Vector3| (model * Vector4::new(v.x, v.y, v.z, 1.0)).xyz();
+        let (world0, world1, world2) = (world(v0), world(v1), world(v2));
+        let normal = normal_matrix.transform(Vector3::cross(v1 - v0, v2 - v0));
+
+        assert!(Vector3::dot(normal, world1 - world0).abs() < 1e-4);
+        assert!(Vector3::dot(normal, world2 - world0).abs() < 1e-4);
+        assert!(Vector3::dot(normal, Vector3::cross(world1 - world0, world2 - world0)) > 0.0);
+    }
+}
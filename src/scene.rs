@@ -0,0 +1,134 @@
+//! Scene Graph
+
+extern crate tdmath;
+
+use self::tdmath::{Vector3, Matrix4, Quaternion};
+use transform::{quaternion_identity, rotation_matrix};
+
+/// Scene node with a local transform and an optional model.
+pub struct SceneNode {
+    pub name: String,
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+    pub model: Option<usize>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+impl SceneNode {
+    /// Returns a new `SceneNode` with an identity transform.
+    fn new(name: &str) -> SceneNode {
+        SceneNode {
+            name: name.to_owned(),
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: quaternion_identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            model: None,
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the transform of this node relative to its parent.
+    pub fn local_matrix(&self) -> Matrix4 {
+        Matrix4::translation(self.translation.x, self.translation.y, self.translation.z)
+            * rotation_matrix(self.rotation)
+            * Matrix4::scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    /// Returns the index of the parent node.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Returns the indices of the child nodes.
+    pub fn children(&self) -> &Vec<usize> {
+        &self.children
+    }
+}
+
+/// Scene graph of transformed nodes.
+pub struct Scene {
+    nodes: Vec<SceneNode>,
+}
+
+impl Scene {
+    /// Returns a new empty `Scene`.
+    pub fn new() -> Scene {
+        Scene {
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Adds a node under `parent` and returns its index.
+    /// Nodes without a parent are placed at the root of the scene.
+    pub fn add_node(&mut self, name: &str, parent: Option<usize>) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(SceneNode::new(name));
+        self.set_parent(id, parent);
+        id
+    }
+
+    /// Moves the node `id` under `parent`.
+    /// Parenting a node to itself or one of its descendants is ignored.
+    pub fn set_parent(&mut self, id: usize, parent: Option<usize>) {
+        if let Some(parent_id) = parent {
+            if self.is_descendant(parent_id, id) {
+                return;
+            }
+        }
+
+        if let Some(old_parent) = self.nodes[id].parent {
+            self.nodes[old_parent].children.retain(|&child| child != id);
+        }
+
+        self.nodes[id].parent = parent;
+        if let Some(parent_id) = parent {
+            self.nodes[parent_id].children.push(id);
+        }
+    }
+
+    /// Returns true if `id` is `ancestor` or is below it in the hierarchy.
+    fn is_descendant(&self, id: usize, ancestor: usize) -> bool {
+        let mut current = Some(id);
+        while let Some(node_id) = current {
+            if node_id == ancestor {
+                return true;
+            }
+            current = self.nodes[node_id].parent;
+        }
+
+        false
+    }
+
+    /// Returns the node at `id`.
+    pub fn node(&self, id: usize) -> &SceneNode {
+        &self.nodes[id]
+    }
+
+    /// Returns the mutable node at `id`.
+    pub fn node_mut(&mut self, id: usize) -> &mut SceneNode {
+        &mut self.nodes[id]
+    }
+
+    /// Returns a list of all nodes.
+    pub fn nodes(&self) -> &Vec<SceneNode> {
+        &self.nodes
+    }
+
+    /// Returns the transform from the node's local space to world space.
+    pub fn world_matrix(&self, id: usize) -> Matrix4 {
+        let node = &self.nodes[id];
+        match node.parent {
+            Some(parent) => self.world_matrix(parent) * node.local_matrix(),
+            None => node.local_matrix(),
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene::new()
+    }
+}
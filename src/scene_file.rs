@@ -0,0 +1,265 @@
+//! Scene File
+
+extern crate tdmath;
+
+use self::tdmath::{Vector3, Quaternion};
+use camera::Projection;
+use light::Light;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use toml;
+use toml::Value;
+use toml::value::Table;
+use transform::quaternion_identity;
+
+/// Model entry in a scene file
+pub struct ModelDescription {
+    pub name: String,
+    pub path: PathBuf,
+    pub material: Option<PathBuf>,
+    pub parent: Option<String>,
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+}
+
+/// Camera entry in a scene file
+#[derive(Debug, Copy, Clone)]
+pub struct CameraDescription {
+    pub position: Vector3,
+    pub target: Vector3,
+    pub projection: Projection,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl CameraDescription {
+    /// Returns a new `CameraDescription` from a TOML camera table.
+    /// Missing values are taken from the default camera.
+    pub fn from_toml(value: &Value) -> CameraDescription {
+        let default = CameraDescription::default();
+
+        let projection = match value.get("projection").and_then(|p| p.as_str()) {
+            Some("orthographic") => Projection::Orthographic(value.get("scale").map(as_f32).unwrap_or(5.0)),
+            Some("perspective") | None => Projection::Perspective(value.get("fov").map(as_f32).unwrap_or(60.0)),
+            Some(other) => panic!("Unknown camera projection: {}", other),
+        };
+
+        CameraDescription {
+            position: get_vector(value, "position", default.position),
+            target: get_vector(value, "target", default.target),
+            projection,
+            near: value.get("near").map(as_f32).unwrap_or(default.near),
+            far: value.get("far").map(as_f32).unwrap_or(default.far),
+        }
+    }
+
+    /// Returns this camera as a TOML table.
+    pub fn to_toml(&self) -> Value {
+        let mut camera = Table::new();
+        camera.insert("position".to_owned(), vector_value(self.position));
+        camera.insert("target".to_owned(), vector_value(self.target));
+        match self.projection {
+            Projection::Perspective(fov) => {
+                camera.insert("projection".to_owned(), Value::String("perspective".to_owned()));
+                camera.insert("fov".to_owned(), Value::Float(f64::from(fov)));
+            },
+            Projection::Orthographic(scale) => {
+                camera.insert("projection".to_owned(), Value::String("orthographic".to_owned()));
+                camera.insert("scale".to_owned(), Value::Float(f64::from(scale)));
+            },
+        }
+        camera.insert("near".to_owned(), Value::Float(f64::from(self.near)));
+        camera.insert("far".to_owned(), Value::Float(f64::from(self.far)));
+        Value::Table(camera)
+    }
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        CameraDescription {
+            position: Vector3::new(0.0, 0.0, 10.0),
+            target: Vector3::new(0.0, 0.0, 0.0),
+            projection: Projection::Perspective(60.0),
+            near: 0.1,
+            far: 50.0,
+        }
+    }
+}
+
+/// Scene file contents
+pub struct SceneDescription {
+    pub models: Vec<ModelDescription>,
+    pub lights: Vec<Light>,
+    pub camera: CameraDescription,
+    pub background: [u8; 3],
+    /// Material used by models without their own, as given with `--material`
+    pub material: Option<PathBuf>,
+}
+
+impl SceneDescription {
+    /// Returns a new `SceneDescription` loaded from the TOML file at `scene_path`.
+    /// Model and material paths are relative to the scene file.
+    pub fn load(scene_path: &Path) -> SceneDescription {
+        let mut f = File::open(scene_path).unwrap_or_else(|_| panic!("File not found: {:?}", scene_path));
+        let mut file_contents = String::new();
+        f.read_to_string(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", scene_path));
+
+        let toml = file_contents.parse::<Value>().unwrap_or_else(|_| panic!("Unable to parse scene: {:?}", scene_path));
+        let scene_dir = scene_path.parent().unwrap_or_else(|| Path::new(""));
+
+        let models = match toml.get("models").and_then(|m| m.as_array()) {
+            Some(models) => models.iter().map(|m| parse_model(m, scene_dir)).collect(),
+            None => Vec::new(),
+        };
+
+        let lights = match toml.get("lights").and_then(|l| l.as_array()) {
+            Some(lights) => lights.iter().map(parse_light).collect(),
+            None => vec![Light::default()],
+        };
+
+        let camera = match toml.get("camera") {
+            Some(camera) => CameraDescription::from_toml(camera),
+            None => CameraDescription::default(),
+        };
+
+        let background = match toml.get("background").and_then(|b| b.as_array()) {
+            Some(b) if b.len() == 3 => {
+                let channel = |v: &Value| match v.as_integer() {
+                    Some(c) if (0..=255).contains(&c) => c as u8,
+                    _ => panic!("Invalid background color in scene, expected 0 to 255: {:?}", scene_path),
+                };
+                [channel(&b[0]), channel(&b[1]), channel(&b[2])]
+            },
+            _ => [65, 65, 65],
+        };
+
+        let material = toml.get("material").and_then(|m| m.as_str()).map(|m| scene_dir.join(m));
+
+        SceneDescription {
+            models,
+            lights,
+            camera,
+            background,
+            material,
+        }
+    }
+
+    /// Saves this scene to a TOML file at `scene_path`.
+    /// Model and material paths are written relative to the scene file when possible.
+    pub fn save(&self, scene_path: &Path) {
+        let scene_dir = scene_path.parent().unwrap_or_else(|| Path::new(""));
+        let mut root = Table::new();
+
+        root.insert("background".to_owned(), Value::Array(self.background.iter().map(|&c| Value::Integer(i64::from(c))).collect()));
+
+        if let Some(ref material) = self.material {
+            root.insert("material".to_owned(), path_value(material, scene_dir));
+        }
+
+        root.insert("camera".to_owned(), self.camera.to_toml());
+
+        let lights = self.lights.iter().map(|light| {
+            let mut table = Table::new();
+            table.insert("direction".to_owned(), vector_value(light.direction));
+            table.insert("intensity".to_owned(), Value::Float(f64::from(light.intensity)));
+            Value::Table(table)
+        }).collect();
+        root.insert("lights".to_owned(), Value::Array(lights));
+
+        let models = self.models.iter().map(|model| {
+            let mut table = Table::new();
+            table.insert("name".to_owned(), Value::String(model.name.clone()));
+            table.insert("path".to_owned(), path_value(&model.path, scene_dir));
+            if let Some(ref material) = model.material {
+                table.insert("material".to_owned(), path_value(material, scene_dir));
+            }
+            if let Some(ref parent) = model.parent {
+                table.insert("parent".to_owned(), Value::String(parent.clone()));
+            }
+            table.insert("translation".to_owned(), vector_value(model.translation));
+            table.insert("rotation".to_owned(), Value::Array(vec![
+                Value::Float(f64::from(model.rotation.x)),
+                Value::Float(f64::from(model.rotation.y)),
+                Value::Float(f64::from(model.rotation.z)),
+                Value::Float(f64::from(model.rotation.w)),
+            ]));
+            table.insert("scale".to_owned(), vector_value(model.scale));
+            Value::Table(table)
+        }).collect();
+        root.insert("models".to_owned(), Value::Array(models));
+
+        let contents = toml::to_string(&Value::Table(root)).unwrap_or_else(|_| panic!("Unable to serialize scene: {:?}", scene_path));
+        let mut f = File::create(scene_path).unwrap_or_else(|_| panic!("Unable to create file: {:?}", scene_path));
+        f.write_all(contents.as_bytes()).unwrap_or_else(|_| panic!("Error writing file: {:?}", scene_path));
+    }
+}
+
+/// Parses a `[[models]]` entry.
+fn parse_model(value: &Value, scene_dir: &Path) -> ModelDescription {
+    let path = match value.get("path").and_then(|p| p.as_str()) {
+        Some(path) => scene_dir.join(path),
+        None => panic!("Scene model is missing a path"),
+    };
+
+    let name = match value.get("name").and_then(|n| n.as_str()) {
+        Some(name) => name.to_owned(),
+        None => path.file_stem().and_then(|n| n.to_str()).unwrap_or("Model").to_owned(),
+    };
+
+    let rotation = match value.get("rotation").and_then(|r| r.as_array()) {
+        Some(r) if r.len() == 4 => Quaternion { x: as_f32(&r[0]), y: as_f32(&r[1]), z: as_f32(&r[2]), w: as_f32(&r[3]) },
+        _ => quaternion_identity(),
+    };
+
+    ModelDescription {
+        name,
+        path,
+        material: value.get("material").and_then(|m| m.as_str()).map(|m| scene_dir.join(m)),
+        parent: value.get("parent").and_then(|p| p.as_str()).map(|p| p.to_owned()),
+        translation: get_vector(value, "translation", Vector3::new(0.0, 0.0, 0.0)),
+        rotation,
+        scale: get_vector(value, "scale", Vector3::new(1.0, 1.0, 1.0)),
+    }
+}
+
+/// Parses a `[[lights]]` entry.
+fn parse_light(value: &Value) -> Light {
+    let intensity = value.get("intensity").map(as_f32).unwrap_or(1.0);
+    Light::new(get_vector(value, "direction", Vector3::new(0.0, 0.0, -1.0)), intensity)
+}
+
+/// Returns the numeric value of a TOML float or integer.
+fn as_f32(value: &Value) -> f32 {
+    match *value {
+        Value::Float(f) => f as f32,
+        Value::Integer(i) => i as f32,
+        _ => panic!("Expected a number in scene file, found: {}", value),
+    }
+}
+
+/// Returns the `[x, y, z]` array at `key`, or `default` if it is not set.
+fn get_vector(value: &Value, key: &str, default: Vector3) -> Vector3 {
+    match value.get(key).and_then(|v| v.as_array()) {
+        Some(v) if v.len() == 3 => Vector3::new(as_f32(&v[0]), as_f32(&v[1]), as_f32(&v[2])),
+        Some(_) => panic!("Expected [x, y, z] for {} in scene file", key),
+        None => default,
+    }
+}
+
+/// Returns `vector` as a TOML array.
+fn vector_value(vector: Vector3) -> Value {
+    Value::Array(vec![Value::Float(f64::from(vector.x)), Value::Float(f64::from(vector.y)), Value::Float(f64::from(vector.z))])
+}
+
+/// Returns `path` as a TOML string, relative to `base` if possible.
+/// Paths outside of `base` are written as absolute paths.
+fn path_value(path: &Path, base: &Path) -> Value {
+    let path = match path.strip_prefix(base) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path.canonicalize().unwrap_or_else(|_| path.to_path_buf()),
+    };
+
+    Value::String(path.to_string_lossy().into_owned())
+}
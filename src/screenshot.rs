@@ -0,0 +1,44 @@
+//! Screenshots
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use image;
+use renderer::Renderer;
+
+/// Saves the scene to a timestamped PNG in the working directory and returns its path.
+/// The scene is rendered at `scale` times the current resolution when `scale` is greater than 1,
+/// otherwise the last rendered frame is saved.
+pub fn save(renderer: &mut Renderer, scale: u32) -> PathBuf {
+    let path = timestamped_path();
+
+    let (width, height) = renderer.size();
+    let outline_width = renderer.outline_width();
+    let scale = scale.max(1) as usize;
+    if scale > 1 {
+        // Toon outlines are measured in pixels, so they are widened to keep their thickness in the larger image.
+        renderer.set_outline_width(outline_width * scale as u32);
+        renderer.resize(width * scale, height * scale);
+        renderer.render();
+    }
+
+    {
+        let framebuffer = renderer.framebuffer();
+        image::save_buffer(&path, framebuffer.pixels(), framebuffer.width() as u32, framebuffer.height() as u32, image::RGB(8))
+            .unwrap_or_else(|_| panic!("Unable to write image: {:?}", path));
+    }
+
+    if scale > 1 {
+        renderer.resize(width, height);
+        renderer.set_outline_width(outline_width);
+    }
+
+    path
+}
+
+/// Returns a screenshot path named after the current time, e.g. `screenshot_1528123456789.png`.
+fn timestamped_path() -> PathBuf {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let millis = time.as_secs() * 1000 + u64::from(time.subsec_nanos() / 1_000_000);
+
+    PathBuf::from(format!("screenshot_{}.png", millis))
+}
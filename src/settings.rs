@@ -2,45 +2,320 @@
 
 extern crate cmdpro;
 
+use std::fs::File;
+use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use cmdpro::{CommandLineProcessor, ParameterValue};
+use toml::Value;
+use aov::{Aov, DepthFormat};
+use postprocess::Downsample;
+use debug_view::DebugView;
+use shading::Shading;
+use toon::Toon;
+use ssao::Ssao;
+use fog::{Fog, FogMode};
 
 /// Application Settings
 pub struct Settings {
-    model_path: PathBuf,
+    model_path: Option<PathBuf>,
+    scene_path: Option<PathBuf>,
     width: u32,
     height: u32,
+    near: Option<f32>,
+    far: Option<f32>,
+    fov: Option<f32>,
+    ortho_scale: Option<f32>,
+    dolly_zoom: bool,
+    transition_time: f32,
+    turntable_path: Option<PathBuf>,
+    turntable_frames: u32,
+    turntable_elevation: Option<f32>,
+    screenshot_scale: u32,
+    render_path: Option<PathBuf>,
+    aovs: Vec<Aov>,
+    depth_format: DepthFormat,
+    msaa_samples: u32,
+    render_scale: u32,
+    downsample: Downsample,
+    fxaa: bool,
+    debug_view: DebugView,
+    order_independent_transparency: bool,
+    shading: Shading,
+    toon_bands: u32,
+    toon_ramp: Option<PathBuf>,
+    outline_color: [u8; 3],
+    outline_width: u32,
+    crease_angle: f32,
+    ssao_enabled: bool,
+    ssao_radius: f32,
+    ssao_samples: u32,
+    ssao_blur: u32,
+    fog_mode: Option<FogMode>,
+    fog_color: Option<[u8; 3]>,
+    fog_density: f32,
+    fog_start: f32,
+    fog_end: Option<f32>,
+    fog_height: f32,
+    fog_height_falloff: f32,
 }
 
 impl Settings {
     /// Returns a new `Settings` from a `CommandLineProcessor`.
-    /// Default values are used if not passed into the program.
+    /// Values not passed into the program are read from the `--config` file if one is given,
+    /// otherwise default values are used.
     pub fn from_commandline(commandline: &CommandLineProcessor) -> Settings {
+        let config = match commandline.get_parameter_value("config") {
+            ParameterValue::Path(path) => Some(load_config(path)),
+            _ => None,
+        };
+        let config_float = |key: &str| config.as_ref()
+            .and_then(|c| c.get(key))
+            .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+            .map(|v| v as f32);
+        let config_uint = |key: &str| config.as_ref()
+            .and_then(|c| c.get(key))
+            .and_then(|v| v.as_integer())
+            .map(|v| {
+                if v < 0 || v > i64::from(u32::max_value()) {
+                    panic!("Invalid value for {}: {}", key, v);
+                }
+                v as u32
+            });
+
+        let scene_path = match commandline.get_parameter_value("scene") {
+            ParameterValue::Path(path) => Some(PathBuf::from(path)),
+            _ => None,
+        };
+
         let model_path = match commandline.get_parameter_value("model") {
-            ParameterValue::Path(path) => path,
+            ParameterValue::Path(path) => Some(PathBuf::from(path)),
+            _ if scene_path.is_some() => None,
             _ => panic!("Model path not set"),
         };
 
         let width = match commandline.get_parameter_value("width") {
             ParameterValue::UInteger(width) => *width,
-            _ => 800,
+            _ => config_uint("width").unwrap_or(800),
         };
 
         let height = match commandline.get_parameter_value("height") {
             ParameterValue::UInteger(height) => *height,
-            _ => 800,
+            _ => config_uint("height").unwrap_or(800),
+        };
+
+        let near = match commandline.get_parameter_value("near") {
+            ParameterValue::Float(near) => Some(*near),
+            _ => config_float("near"),
+        };
+
+        let far = match commandline.get_parameter_value("far") {
+            ParameterValue::Float(far) => Some(*far),
+            _ => config_float("far"),
+        };
+
+        let fov = match commandline.get_parameter_value("fov") {
+            ParameterValue::Float(fov) => Some(*fov),
+            _ => config_float("fov"),
+        };
+
+        let ortho_scale = match commandline.get_parameter_value("ortho-scale") {
+            ParameterValue::Float(scale) => Some(*scale),
+            _ => config_float("ortho_scale"),
+        };
+
+        let dolly_zoom = config.as_ref()
+            .and_then(|c| c.get("dolly_zoom"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let transition_time = match commandline.get_parameter_value("transition-time") {
+            ParameterValue::Float(time) => *time,
+            _ => config_float("transition_time").unwrap_or(0.5),
+        };
+
+        let turntable_path = match commandline.get_parameter_value("turntable") {
+            ParameterValue::Path(path) => Some(PathBuf::from(path)),
+            _ => None,
+        };
+
+        let turntable_frames = match commandline.get_parameter_value("frames") {
+            ParameterValue::UInteger(frames) => *frames,
+            _ => config_uint("turntable_frames").unwrap_or(36),
+        };
+
+        let turntable_elevation = match commandline.get_parameter_value("elevation") {
+            ParameterValue::Float(elevation) => Some(*elevation),
+            _ => config_float("turntable_elevation"),
+        };
+
+        let screenshot_scale = match commandline.get_parameter_value("screenshot-scale") {
+            ParameterValue::UInteger(scale) => *scale,
+            _ => config_uint("screenshot_scale").unwrap_or(1),
+        };
+
+        let render_path = match commandline.get_parameter_value("render") {
+            ParameterValue::Path(path) => Some(PathBuf::from(path)),
+            _ => None,
+        };
+
+        let aovs = config.as_ref()
+            .and_then(|c| c.get("aovs"))
+            .and_then(|v| v.as_array())
+            .map(|names| names.iter()
+                .filter_map(|name| name.as_str())
+                .map(|name| Aov::from_name(name).unwrap_or_else(|| panic!("Unknown AOV: {}", name)))
+                .collect())
+            .unwrap_or_else(Vec::new);
+
+        let depth_format = match config.as_ref().and_then(|c| c.get("depth_format")).and_then(|v| v.as_str()) {
+            Some("pfm") => DepthFormat::Pfm,
+            Some("png") | None => DepthFormat::Png,
+            Some(format) => panic!("Unknown depth format: {}", format),
+        };
+
+        let msaa_samples = match commandline.get_parameter_value("msaa") {
+            ParameterValue::UInteger(samples) => *samples,
+            _ => config_uint("msaa").unwrap_or(1).max(1),
+        };
+
+        let render_scale = match commandline.get_parameter_value("render-scale") {
+            ParameterValue::UInteger(scale) => *scale,
+            _ => config_uint("render_scale").unwrap_or(1),
+        };
+
+        let downsample = match config.as_ref().and_then(|c| c.get("downsample")).and_then(|v| v.as_str()) {
+            Some(name) => Downsample::from_name(name).unwrap_or_else(|| panic!("Unknown downsample filter: {}", name)),
+            None => Downsample::Box,
+        };
+
+        let fxaa = config.as_ref()
+            .and_then(|c| c.get("fxaa"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let debug_view = match config.as_ref().and_then(|c| c.get("debug_view")).and_then(|v| v.as_str()) {
+            Some(name) => DebugView::from_name(name).unwrap_or_else(|| panic!("Unknown debug view: {}", name)),
+            None => DebugView::Shaded,
+        };
+
+        let order_independent_transparency = match config.as_ref().and_then(|c| c.get("transparency")).and_then(|v| v.as_str()) {
+            Some("abuffer") => true,
+            Some("sorted") | None => false,
+            Some(mode) => panic!("Unknown transparency mode: {}", mode),
         };
 
+        let shading = match config.as_ref().and_then(|c| c.get("shading")).and_then(|v| v.as_str()) {
+            Some(name) => Shading::from_name(name).unwrap_or_else(|| panic!("Unknown shading mode: {}", name)),
+            None => Shading::Lit,
+        };
+
+        let toon_defaults = Toon::default();
+
+        let toon_bands = config_uint("toon_bands").unwrap_or(toon_defaults.bands);
+
+        let toon_ramp = config.as_ref()
+            .and_then(|c| c.get("toon_ramp"))
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from);
+
+        let config_color = |key: &str| config.as_ref()
+            .and_then(|c| c.get(key))
+            .and_then(|v| v.as_array())
+            .map(|color| {
+                let channel = |i: usize| color.get(i).and_then(|v| v.as_integer()).unwrap_or(0).max(0).min(255) as u8;
+                [channel(0), channel(1), channel(2)]
+            });
+
+        let outline_color = config_color("outline_color").unwrap_or(toon_defaults.outline_color);
+
+        let outline_width = config_uint("outline_width").unwrap_or(toon_defaults.outline_width);
+
+        let crease_angle = config_float("crease_angle").unwrap_or(toon_defaults.crease_angle);
+
+        let ssao_enabled = config.as_ref()
+            .and_then(|c| c.get("ssao"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let ssao_defaults = Ssao::default();
+
+        let ssao_radius = config_float("ssao_radius").unwrap_or(ssao_defaults.radius);
+
+        let ssao_samples = config_uint("ssao_samples").unwrap_or(ssao_defaults.sample_count).max(1);
+
+        let ssao_blur = config_uint("ssao_blur").unwrap_or(ssao_defaults.blur_radius);
+
+        let fog_mode = config.as_ref()
+            .and_then(|c| c.get("fog"))
+            .and_then(|v| v.as_str())
+            .map(|name| FogMode::from_name(name).unwrap_or_else(|| panic!("Unknown fog mode: {}", name)));
+
+        let fog_color = config_color("fog_color");
+
+        let fog_defaults = Fog::default();
+
+        let fog_density = config_float("fog_density").unwrap_or(fog_defaults.density);
+
+        let fog_start = config_float("fog_start").unwrap_or(fog_defaults.start);
+
+        let fog_end = config_float("fog_end");
+
+        let fog_height = config_float("fog_height").unwrap_or(fog_defaults.height);
+
+        let fog_height_falloff = config_float("fog_height_falloff").unwrap_or(fog_defaults.height_falloff);
+
         Settings {
-            model_path: PathBuf::from(model_path),
+            model_path,
+            scene_path,
             width,
             height,
+            near,
+            far,
+            fov,
+            ortho_scale,
+            dolly_zoom,
+            transition_time,
+            turntable_path,
+            turntable_frames,
+            turntable_elevation,
+            screenshot_scale,
+            render_path,
+            aovs,
+            depth_format,
+            msaa_samples,
+            render_scale,
+            downsample,
+            fxaa,
+            debug_view,
+            order_independent_transparency,
+            shading,
+            toon_bands,
+            toon_ramp,
+            outline_color,
+            outline_width,
+            crease_angle,
+            ssao_enabled,
+            ssao_radius,
+            ssao_samples,
+            ssao_blur,
+            fog_mode,
+            fog_color,
+            fog_density,
+            fog_start,
+            fog_end,
+            fog_height,
+            fog_height_falloff,
         }
     }
 
     /// Returns the model path.
-    pub fn model_path(&self) -> &Path {
-        self.model_path.as_path()
+    pub fn model_path(&self) -> Option<&Path> {
+        self.model_path.as_ref().map(|p| p.as_path())
+    }
+
+    /// Returns the scene file path.
+    pub fn scene_path(&self) -> Option<&Path> {
+        self.scene_path.as_ref().map(|p| p.as_path())
     }
 
     /// Returns the target window width.
@@ -52,4 +327,165 @@ impl Settings {
     pub fn height(&self) -> u32 {
         self.height
     }
+
+    /// Returns the camera's near plane distance if set.
+    pub fn near(&self) -> Option<f32> {
+        self.near
+    }
+
+    /// Returns the camera's far plane distance if set.
+    pub fn far(&self) -> Option<f32> {
+        self.far
+    }
+
+    /// Returns the camera's perspective field of view in degrees if set.
+    pub fn fov(&self) -> Option<f32> {
+        self.fov
+    }
+
+    /// Returns the camera's orthographic scale if set.
+    pub fn ortho_scale(&self) -> Option<f32> {
+        self.ortho_scale
+    }
+
+    /// Returns true if zooming should move the camera instead of changing the FOV.
+    pub fn dolly_zoom(&self) -> bool {
+        self.dolly_zoom
+    }
+
+    /// Returns the duration of camera transitions in seconds.
+    pub fn transition_time(&self) -> f32 {
+        self.transition_time
+    }
+
+    /// Returns the output path of a turntable export if one was requested.
+    pub fn turntable_path(&self) -> Option<&Path> {
+        self.turntable_path.as_ref().map(|p| p.as_path())
+    }
+
+    /// Returns the number of frames in a turntable export.
+    pub fn turntable_frames(&self) -> u32 {
+        self.turntable_frames
+    }
+
+    /// Returns the camera elevation in degrees for a turntable export if set.
+    pub fn turntable_elevation(&self) -> Option<f32> {
+        self.turntable_elevation
+    }
+
+    /// Returns the multiple of the window resolution screenshots are rendered at.
+    pub fn screenshot_scale(&self) -> u32 {
+        self.screenshot_scale
+    }
+
+    /// Returns the output path of a single headless render if one was requested.
+    pub fn render_path(&self) -> Option<&Path> {
+        self.render_path.as_ref().map(|p| p.as_path())
+    }
+
+    /// Returns the extra passes written alongside headless renders.
+    pub fn aovs(&self) -> &[Aov] {
+        &self.aovs
+    }
+
+    /// Returns the file format of the depth pass.
+    pub fn depth_format(&self) -> DepthFormat {
+        self.depth_format
+    }
+
+    /// Returns the number of MSAA samples per pixel.
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    /// Returns the supersampling render scale, 1 if supersampling is disabled.
+    pub fn render_scale(&self) -> u32 {
+        self.render_scale
+    }
+
+    /// Returns the filter used to downsample supersampled frames.
+    pub fn downsample(&self) -> Downsample {
+        self.downsample
+    }
+
+    /// Returns true if FXAA should be applied to the final frame.
+    pub fn fxaa(&self) -> bool {
+        self.fxaa
+    }
+
+    /// Returns the debug view to render with.
+    pub fn debug_view(&self) -> DebugView {
+        self.debug_view
+    }
+
+    /// Returns true if blended materials should be composited with the A-buffer.
+    pub fn order_independent_transparency(&self) -> bool {
+        self.order_independent_transparency
+    }
+
+    /// Returns the shading mode to render with.
+    pub fn shading(&self) -> Shading {
+        self.shading
+    }
+
+    /// Returns the toon shading settings, loading the ramp texture if one is set.
+    pub fn toon(&self) -> Toon {
+        let mut toon = Toon {
+            bands: self.toon_bands,
+            ramp: None,
+            outline_color: self.outline_color,
+            outline_width: self.outline_width,
+            crease_angle: self.crease_angle,
+        };
+        if let Some(ref path) = self.toon_ramp {
+            toon.load_ramp(path);
+        }
+
+        toon
+    }
+
+    /// Returns true if screen space ambient occlusion is enabled.
+    pub fn ssao_enabled(&self) -> bool {
+        self.ssao_enabled
+    }
+
+    /// Returns the ambient occlusion radius, sample count and blur.
+    pub fn ssao(&self) -> Ssao {
+        Ssao {
+            radius: self.ssao_radius,
+            sample_count: self.ssao_samples,
+            blur_radius: self.ssao_blur,
+        }
+    }
+
+    /// Returns true if fog is enabled.
+    pub fn fog_enabled(&self) -> bool {
+        self.fog_mode.is_some()
+    }
+
+    /// Returns the fog settings, linear fog if no mode is set.
+    pub fn fog(&self) -> Fog {
+        Fog {
+            mode: self.fog_mode.unwrap_or(FogMode::Linear),
+            density: self.fog_density,
+            start: self.fog_start,
+            end: self.fog_end,
+            height: self.fog_height,
+            height_falloff: self.fog_height_falloff,
+        }
+    }
+
+    /// Returns the fog color, which replaces the background color if set.
+    pub fn fog_color(&self) -> Option<[u8; 3]> {
+        self.fog_color
+    }
+}
+
+/// Loads the TOML config file at `config_path`.
+fn load_config(config_path: &Path) -> Value {
+    let mut f = File::open(config_path).unwrap_or_else(|_| panic!("File not found: {:?}", config_path));
+    let mut file_contents = String::new();
+    f.read_to_string(&mut file_contents).unwrap_or_else(|_| panic!("Error reading file: {:?}", config_path));
+
+    file_contents.parse::<Value>().unwrap_or_else(|_| panic!("Unable to parse config: {:?}", config_path))
 }
@@ -0,0 +1,62 @@
+//! Shading Modes
+
+extern crate tdmath;
+
+use image::{DynamicImage, GenericImage};
+use self::tdmath::Vector3;
+
+/// How the lighting of a surface is computed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shading {
+    /// Diffuse lighting from the scene lights
+    Lit,
+    /// Lighting quantized into flat bands with outlines
+    Toon,
+    /// Lighting read from a matcap image by the view space normal
+    Matcap,
+}
+
+impl Shading {
+    /// Returns the `Shading` mode with the config name `name`.
+    pub fn from_name(name: &str) -> Option<Shading> {
+        match name {
+            "lit" => Some(Shading::Lit),
+            "toon" => Some(Shading::Toon),
+            "matcap" => Some(Shading::Matcap),
+            _ => None,
+        }
+    }
+
+    /// Returns the mode after this one in the viewer's cycle.
+    pub fn next(&self) -> Shading {
+        match *self {
+            Shading::Lit => Shading::Toon,
+            Shading::Toon => Shading::Matcap,
+            Shading::Matcap => Shading::Lit,
+        }
+    }
+
+    /// Returns the text representation of the mode.
+    pub fn name(&self) -> &str {
+        match *self {
+            Shading::Lit => "Lit",
+            Shading::Toon => "Toon",
+            Shading::Matcap => "Matcap",
+        }
+    }
+}
+
+/// Returns the color of the `matcap` sphere image where it faces `view_normal`, from 0 to 1.
+/// The sphere is inscribed in the image, so normals facing right sample the right edge
+/// and normals facing up sample the top edge.
+pub fn sample_matcap(matcap: &DynamicImage, view_normal: Vector3) -> Vector3 {
+    let (width, height) = (matcap.width(), matcap.height());
+    let u = (view_normal.x * 0.5 + 0.5).max(0.0).min(1.0);
+    let v = (0.5 - view_normal.y * 0.5).max(0.0).min(1.0);
+
+    let x = ((u * width as f32) as u32).min(width - 1);
+    let y = ((v * height as f32) as u32).min(height - 1);
+    let color = matcap.get_pixel(x, y);
+
+    Vector3::new(f32::from(color.data[0]), f32::from(color.data[1]), f32::from(color.data[2])) / 255.0
+}
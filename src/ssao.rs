@@ -0,0 +1,311 @@
+//! Screen Space Ambient Occlusion
+
+extern crate tdmath;
+
+use std::f32;
+use std::f32::consts::PI;
+use self::tdmath::{Vector3, Vector4, Matrix4};
+use framebuffer::FrameBuffer;
+use zbuffer::ZBuffer;
+
+/// Depth differences below this fraction of the radius do not occlude, avoiding self-occlusion on flat surfaces.
+const DEPTH_BIAS: f32 = 0.025;
+
+/// Width of the tiled pattern of kernel rotations. The blur should cover it to hide the pattern.
+const NOISE_SIZE: usize = 4;
+
+/// Ambient occlusion settings.
+pub struct Ssao {
+    /// Radius of the sampled hemisphere in scene units
+    pub radius: f32,
+    /// Number of depth samples taken per pixel
+    pub sample_count: u32,
+    /// Radius in pixels of the blur applied to the occlusion, 0 disables the blur
+    pub blur_radius: u32,
+}
+
+impl Default for Ssao {
+    fn default() -> Self {
+        Ssao {
+            radius: 0.5,
+            sample_count: 16,
+            blur_radius: 2,
+        }
+    }
+}
+
+impl Ssao {
+    /// Darkens the ambient light recorded in `buffers` by how occluded each pixel of `framebuffer` is,
+    /// estimated from the depths in `zbuffer` and the normals in `buffers`.
+    /// Occluded samples are rewritten from their recorded colors, so light clipped at white is darkened correctly.
+    pub fn apply(&self, framebuffer: &mut FrameBuffer, zbuffer: &ZBuffer, buffers: &mut SsaoBuffers, projection: Matrix4, near: f32, far: f32) {
+        let (width, height) = (buffers.width, buffers.height);
+        let unproject = Unprojection::new(projection, near, far);
+
+        // The nearest sample of each pixel is taken as its surface.
+        let depths = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let z = (0..zbuffer.sample_count())
+                    .map(|sample| zbuffer.sample(x, y, sample))
+                    .fold(f32::MAX, f32::min);
+                if z == f32::MAX { f32::INFINITY } else { unproject.depth(z) }
+            })
+            .collect::<Vec<_>>();
+
+        let kernel = kernel(self.sample_count.max(1) as usize);
+        let bias = DEPTH_BIAS * self.radius;
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = x + width * y;
+                if depths[i] == f32::INFINITY {
+                    buffers.occlusion[i] = 1.0;
+                    continue;
+                }
+
+                let position = unproject.position(x as f32 + 0.5, y as f32 + 0.5, width, height, depths[i]);
+                let normal = buffers.view_normals[i];
+
+                // The kernel is rotated per pixel in a repeating pattern, trading banding for noise the blur removes.
+                let angle = (((y % NOISE_SIZE) * NOISE_SIZE + x % NOISE_SIZE) as f32 + 0.5) / (NOISE_SIZE * NOISE_SIZE) as f32 * PI * 2.0;
+                let random = Vector3::new(angle.cos(), angle.sin(), 0.0);
+                let tangent = random - normal * Vector3::dot(random, normal);
+                let tangent = if Vector3::dot(tangent, tangent) > 1e-6 {
+                    tangent.normalized()
+                } else {
+                    Vector3::cross(normal, Vector3::new(1.0, 0.0, 0.0)).normalized()
+                };
+                let bitangent = Vector3::cross(normal, tangent);
+
+                let mut occlusion = 0.0;
+                for k in &kernel {
+                    let sample = position + (tangent * k.x + bitangent * k.y + normal * k.z) * self.radius;
+                    let sample_depth = -sample.z;
+                    if sample_depth <= 0.0 {
+                        continue;
+                    }
+
+                    let (sx, sy) = unproject.pixel(sample, width, height);
+                    if sx < 0.0 || sy < 0.0 || sx >= width as f32 || sy >= height as f32 {
+                        continue;
+                    }
+
+                    let scene_depth = depths[sx as usize + width * sy as usize];
+                    if scene_depth <= sample_depth - bias {
+                        // Occluders far in front of the pixel are separate objects and fade out.
+                        let range = (self.radius / (depths[i] - scene_depth).abs()).min(1.0);
+                        occlusion += range * range * (3.0 - 2.0 * range);
+                    }
+                }
+
+                buffers.occlusion[i] = 1.0 - occlusion / kernel.len() as f32;
+            }
+        }
+
+        self.blur(buffers, &depths);
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = x + width * y;
+                for sample in 0..buffers.sample_count {
+                    let s = i * buffers.sample_count + sample;
+                    let ambient = buffers.ambient[s];
+                    if ambient.x <= 0.0 && ambient.y <= 0.0 && ambient.z <= 0.0 {
+                        continue;
+                    }
+
+                    let color = buffers.colors[s] - ambient * (1.0 - buffers.occlusion[i]);
+                    framebuffer.set_sample([color.x as u8, color.y as u8, color.z as u8], x, y, sample);
+                }
+            }
+        }
+    }
+
+    /// Box blurs the occlusion horizontally and then vertically, only across pixels within the radius in depth
+    /// so that edges stay sharp.
+    fn blur(&self, buffers: &mut SsaoBuffers, depths: &[f32]) {
+        if self.blur_radius == 0 {
+            return;
+        }
+
+        let (width, height) = (buffers.width as isize, buffers.height as isize);
+        let radius = self.blur_radius as isize;
+
+        for &(step_x, step_y) in &[(1, 0), (0, 1)] {
+            let source = buffers.occlusion.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    let i = (x + width * y) as usize;
+                    if depths[i] == f32::INFINITY {
+                        continue;
+                    }
+
+                    let (mut sum, mut count) = (0.0, 0.0);
+                    for offset in -radius..=radius {
+                        let (nx, ny) = (x + offset * step_x, y + offset * step_y);
+                        if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                            continue;
+                        }
+
+                        let n = (nx + width * ny) as usize;
+                        if (depths[n] - depths[i]).abs() <= self.radius {
+                            sum += source[n];
+                            count += 1.0;
+                        }
+                    }
+                    buffers.occlusion[i] = sum / count;
+                }
+            }
+        }
+    }
+}
+
+/// Per-pixel surface values recorded while rendering for the occlusion pass.
+pub struct SsaoBuffers {
+    view_normals: Vec<Vector3>,
+    /// Each sample's shaded color before it is clamped, from 0 to 255
+    colors: Vec<Vector3>,
+    /// The part of each sample's color lit by the ambient light, from 0 to 255
+    ambient: Vec<Vector3>,
+    occlusion: Vec<f32>,
+    width: usize,
+    height: usize,
+    sample_count: usize,
+}
+
+impl SsaoBuffers {
+    /// Returns new empty `SsaoBuffers` with `width` and `height`.
+    pub fn new(width: usize, height: usize) -> SsaoBuffers {
+        let size = width * height;
+        SsaoBuffers {
+            view_normals: vec![Vector3::new(0.0, 0.0, 1.0); size],
+            colors: vec![Vector3::new(0.0, 0.0, 0.0); size],
+            ambient: vec![Vector3::new(0.0, 0.0, 0.0); size],
+            occlusion: vec![1.0; size],
+            width,
+            height,
+            sample_count: 1,
+        }
+    }
+
+    /// Resizes and clears these buffers.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = SsaoBuffers::new(width, height);
+    }
+
+    /// Clears the recorded ambient light so that background pixels are left unchanged,
+    /// making room for `sample_count` samples per pixel.
+    pub fn clear(&mut self, sample_count: usize) {
+        let size = self.width * self.height * sample_count;
+        self.sample_count = sample_count;
+        self.colors.resize(size, Vector3::new(0.0, 0.0, 0.0));
+        self.ambient.clear();
+        self.ambient.resize(size, Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    /// Records the view space normal of the surface visible at `x`, `y`.
+    pub fn set_normal(&mut self, x: usize, y: usize, view_normal: Vector3) {
+        self.view_normals[x + self.width * y] = view_normal;
+    }
+
+    /// Records the unclamped shaded color of `sample` at `x`, `y` and the part of it lit by the ambient light.
+    pub fn set_sample(&mut self, x: usize, y: usize, sample: usize, color: Vector3, ambient: Vector3) {
+        let s = (x + self.width * y) * self.sample_count + sample;
+        self.colors[s] = color;
+        self.ambient[s] = ambient;
+    }
+}
+
+/// Maps between depth buffer values, pixels and view space positions.
+/// The mapping is measured from the projection matrix so that it holds for both projections and any mirroring.
+struct Unprojection {
+    perspective: bool,
+    /// Depth buffer value `z` is `depth_offset + depth_scale * d` for orthographic projections
+    /// and `depth_offset + depth_scale / d` for perspective projections at view depth `d`.
+    depth_offset: f32,
+    depth_scale: f32,
+    /// NDC offset and scale of view space x and y, at a view depth of 1 for perspective projections
+    offset: (f32, f32),
+    scale: (f32, f32),
+}
+
+impl Unprojection {
+    fn new(projection: Matrix4, near: f32, far: f32) -> Unprojection {
+        let ndc = |x: f32, y: f32, depth: f32| {
+            let p = projection * Vector4::new(x, y, -depth, 1.0);
+            Vector3::new(p.x / p.w, p.y / p.w, p.z / p.w)
+        };
+        let w = |depth: f32| (projection * Vector4::new(0.0, 0.0, -depth, 1.0)).w;
+
+        let perspective = (w(near) - w(far)).abs() > f32::EPSILON;
+        let (z_near, z_far) = (ndc(0.0, 0.0, near).z, ndc(0.0, 0.0, far).z);
+        let depth_scale = if perspective {
+            (z_near - z_far) / (1.0 / near - 1.0 / far)
+        } else {
+            (z_far - z_near) / (far - near)
+        };
+        let depth_offset = if perspective { z_near - depth_scale / near } else { z_near - depth_scale * near };
+
+        let origin = ndc(0.0, 0.0, 1.0);
+        let unit = ndc(1.0, 1.0, 1.0);
+
+        Unprojection {
+            perspective,
+            depth_offset,
+            depth_scale,
+            offset: (origin.x, origin.y),
+            scale: (unit.x - origin.x, unit.y - origin.y),
+        }
+    }
+
+    /// Returns the view depth of the depth buffer value `z`.
+    fn depth(&self, z: f32) -> f32 {
+        if self.perspective {
+            self.depth_scale / (z - self.depth_offset)
+        } else {
+            (z - self.depth_offset) / self.depth_scale
+        }
+    }
+
+    /// Returns the view space position at pixel coordinates `x`, `y` and view depth `depth`.
+    fn position(&self, x: f32, y: f32, width: usize, height: usize, depth: f32) -> Vector3 {
+        let ndc_x = x / width as f32 * 2.0 - 1.0;
+        let ndc_y = y / height as f32 * 2.0 - 1.0;
+        let perspective_scale = if self.perspective { depth } else { 1.0 };
+
+        Vector3::new(
+            (ndc_x - self.offset.0) / self.scale.0 * perspective_scale,
+            (ndc_y - self.offset.1) / self.scale.1 * perspective_scale,
+            -depth,
+        )
+    }
+
+    /// Returns the pixel coordinates of the view space `position`.
+    fn pixel(&self, position: Vector3, width: usize, height: usize) -> (f32, f32) {
+        let perspective_scale = if self.perspective { -position.z } else { 1.0 };
+        let ndc_x = position.x / perspective_scale * self.scale.0 + self.offset.0;
+        let ndc_y = position.y / perspective_scale * self.scale.1 + self.offset.1;
+
+        ((ndc_x + 1.0) * 0.5 * width as f32, (ndc_y + 1.0) * 0.5 * height as f32)
+    }
+}
+
+/// Returns `count` points in the unit hemisphere around +z, spread by a golden angle spiral
+/// and placed closer to the center for lower indices so that nearby geometry counts more.
+fn kernel(count: usize) -> Vec<Vector3> {
+    let golden_angle = PI * (3.0 - 5.0f32.sqrt());
+
+    (0..count)
+        .map(|i| {
+            let t = (i as f32 + 0.5) / count as f32;
+            // Directions and distances use different orders so that near samples are not all near the pole.
+            let z = ((i * 7) % count) as f32 / count as f32 * 0.9 + 0.1;
+            let ring = (1.0 - z * z).sqrt();
+            let angle = golden_angle * i as f32;
+            let distance = 0.1 + 0.9 * t * t;
+
+            Vector3::new(ring * angle.cos(), ring * angle.sin(), z) * distance
+        })
+        .collect()
+}
@@ -0,0 +1,222 @@
+//! STL Loader
+
+use tdmath::{Vector3, Vector4};
+use model::Triangle;
+
+/// Size of the binary STL header in bytes.
+const HEADER_SIZE: usize = 80;
+
+/// Size of a single binary STL triangle record in bytes.
+const TRIANGLE_SIZE: usize = 50;
+
+/// Parses an ASCII or binary STL file into a list of flat shaded triangles.
+/// Zero-area facets have no normal and are skipped.
+pub fn parse_stl(bytes: &[u8]) -> Vec<Triangle> {
+    let positions = if is_binary(bytes) {
+        parse_binary(bytes)
+    } else {
+        parse_ascii(&String::from_utf8_lossy(bytes))
+    };
+
+    positions.chunks(3)
+        .filter(|p| p.len() == 3)
+        .filter_map(|p| flat_triangle(p[0], p[1], p[2]))
+        .collect()
+}
+
+/// Returns true if `bytes` contains a binary STL file.
+/// Binary files may also start with "solid", so the size implied by the triangle count is checked.
+/// ASCII files may start with a byte order mark or whitespace before "solid".
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < HEADER_SIZE + 4 {
+        return false;
+    }
+
+    let triangle_count = read_u32(bytes, HEADER_SIZE) as usize;
+    let expected_size = triangle_count.checked_mul(TRIANGLE_SIZE).and_then(|size| size.checked_add(HEADER_SIZE + 4));
+    if expected_size == Some(bytes.len()) {
+        return true;
+    }
+
+    let text = if bytes.starts_with(b"\xEF\xBB\xBF") { &bytes[3..] } else { bytes };
+    let start = text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(text.len());
+    !text[start..].starts_with(b"solid")
+}
+
+/// Returns the vertex positions of a binary STL file.
+/// The triangle count in the header is limited to the records the file can hold, so truncated files load partially.
+fn parse_binary(bytes: &[u8]) -> Vec<Vector3> {
+    let triangle_count = (read_u32(bytes, HEADER_SIZE) as usize).min((bytes.len() - HEADER_SIZE - 4) / TRIANGLE_SIZE);
+    let mut positions = Vec::with_capacity(triangle_count * 3);
+
+    for i in 0..triangle_count {
+        // Skip the stored facet normal, normals are recalculated from the vertices.
+        let offset = HEADER_SIZE + 4 + i * TRIANGLE_SIZE + 12;
+        if offset + 36 > bytes.len() {
+            break;
+        }
+
+        for v in 0..3 {
+            let vertex_offset = offset + v * 12;
+            positions.push(Vector3::new(read_f32(bytes, vertex_offset),
+                                        read_f32(bytes, vertex_offset + 4),
+                                        read_f32(bytes, vertex_offset + 8)));
+        }
+    }
+
+    positions
+}
+
+/// Returns the vertex positions of an ASCII STL file.
+fn parse_ascii(contents: &str) -> Vec<Vector3> {
+    let mut positions = Vec::new();
+
+    for line in contents.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("vertex") {
+            continue;
+        }
+
+        let coords: Vec<f32> = tokens.filter_map(|t| t.parse().ok()).collect();
+        if coords.len() == 3 {
+            positions.push(Vector3::new(coords[0], coords[1], coords[2]));
+        }
+    }
+
+    positions
+}
+
+/// Returns a `Triangle` with the face normal assigned to each vertex.
+/// Returns None if the triangle has no area.
+fn flat_triangle(p0: Vector3, p1: Vector3, p2: Vector3) -> Option<Triangle> {
+    let normal = Vector3::cross(p1 - p0, p2 - p0);
+    let length_squared = Vector3::dot(normal, normal);
+    if length_squared <= 0.0 || !length_squared.is_finite() {
+        return None;
+    }
+    let normal = normal.normalized();
+    let uv = Vector3::new(0.0, 0.0, 0.0);
+    let color = Vector3::new(1.0, 1.0, 1.0);
+
+    Some(Triangle {
+        v0: Vector4::new(p0.x, p0.y, p0.z, 1.0),
+        v1: Vector4::new(p1.x, p1.y, p1.z, 1.0),
+        v2: Vector4::new(p2.x, p2.y, p2.z, 1.0),
+        vt0: uv,
+        vt1: uv,
+        vt2: uv,
+        vn0: normal,
+        vn1: normal,
+        vn2: normal,
+        vc0: color,
+        vc1: color,
+        vc2: color,
+    })
+}
+
+/// Reads a little endian `u32` at `offset`.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from(bytes[offset])
+        | u32::from(bytes[offset + 1]) << 8
+        | u32::from(bytes[offset + 2]) << 16
+        | u32::from(bytes[offset + 3]) << 24
+}
+
+/// Reads a little endian `f32` at `offset`.
+fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_bits(read_u32(bytes, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_STL: &str = "solid test
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 0 1 0
+  endloop
+endfacet
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 1 1
+    vertex 2 2 2
+  endloop
+endfacet
+endsolid test
+";
+
+    /// Returns a binary STL file holding `triangles`, with a header starting with "solid".
+    fn binary_stl(triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut bytes = b"solid binary".to_vec();
+        bytes.resize(HEADER_SIZE, 0);
+        bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+
+        for triangle in triangles {
+            bytes.extend_from_slice(&[0; 12]);
+            for vertex in triangle {
+                for value in vertex {
+                    bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&[0; 2]);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn parses_ascii() {
+        let triangles = parse_stl(ASCII_STL.as_bytes());
+
+        assert_eq!(triangles.len(), 1);
+        assert_eq!((triangles[0].v1.x, triangles[0].v1.y, triangles[0].v1.z), (1.0, 0.0, 0.0));
+        assert_eq!((triangles[0].vn0.x, triangles[0].vn0.y, triangles[0].vn0.z), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn parses_binary_starting_with_solid() {
+        let bytes = binary_stl(&[[[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]]]);
+        let triangles = parse_stl(&bytes);
+
+        assert_eq!(triangles.len(), 1);
+        assert_eq!((triangles[0].v2.x, triangles[0].v2.y, triangles[0].v2.z), (0.0, 1.0, 0.0));
+        assert_eq!((triangles[0].vn0.x, triangles[0].vn0.y, triangles[0].vn0.z), (-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn parses_ascii_with_byte_order_mark() {
+        let mut bytes = b"\xEF\xBB\xBF  \n".to_vec();
+        bytes.extend_from_slice(ASCII_STL.as_bytes());
+
+        assert_eq!(parse_stl(&bytes).len(), 1);
+    }
+
+    #[test]
+    fn limits_truncated_binary_to_its_records() {
+        let mut bytes = binary_stl(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [0.0, 1.0, 1.0]],
+        ]);
+        bytes.truncate(bytes.len() - 10);
+        bytes[0] = b'x';
+        bytes[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&u32::max_value().to_le_bytes());
+
+        assert_eq!(parse_stl(&bytes).len(), 1);
+    }
+
+    #[test]
+    fn skips_degenerate_facets() {
+        let bytes = binary_stl(&[
+            [[0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        ]);
+        let triangles = parse_stl(&bytes);
+
+        assert_eq!(triangles.len(), 1);
+        assert!(triangles.iter().all(|t| t.vn0.x.is_finite() && t.vn0.y.is_finite() && t.vn0.z.is_finite()));
+    }
+}
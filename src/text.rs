@@ -15,6 +15,39 @@ pub enum TextID {
 
     /// Smooth Shading Option Text
     SmoothShading,
+
+    /// Camera Mode Option Text
+    CameraMode,
+
+    /// Selected Scene Node Text
+    Selection,
+
+    /// Zoom Mode Option Text
+    ZoomMode,
+
+    /// MSAA Option Text
+    Msaa,
+
+    /// Supersampling Option Text
+    Supersampling,
+
+    /// FXAA Option Text
+    Fxaa,
+
+    /// Debug View Option Text
+    DebugView,
+
+    /// Transparency Mode Option Text
+    Transparency,
+
+    /// Shading Mode Option Text
+    Shading,
+
+    /// Ambient Occlusion Option Text
+    Ssao,
+
+    /// Fog Option Text
+    Fog,
 }
 
 /// Anchor Position
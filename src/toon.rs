@@ -0,0 +1,111 @@
+//! Toon Shading
+
+extern crate tdmath;
+
+use std::path::Path;
+use image::{self, GenericImage};
+use self::tdmath::Vector3;
+use aov::AovBuffers;
+use framebuffer::FrameBuffer;
+
+/// Neighbors closer than this fraction of a pixel's depth are on the same surface.
+const DEPTH_THRESHOLD: f32 = 0.05;
+
+/// Cel shading and outline settings.
+pub struct Toon {
+    /// Number of flat lighting bands used when there is no ramp
+    pub bands: u32,
+    /// Colors the lighting intensity maps to, from unlit to fully lit
+    pub ramp: Option<Vec<Vector3>>,
+    pub outline_color: [u8; 3],
+    /// Outline thickness in pixels, 0 disables outlines
+    pub outline_width: u32,
+    /// Smallest angle in degrees between neighboring normals that is drawn as a crease
+    pub crease_angle: f32,
+}
+
+impl Toon {
+    /// Loads the ramp from the first row of the image at `path`.
+    pub fn load_ramp(&mut self, path: &Path) {
+        let image = image::open(path).unwrap_or_else(|_| panic!("Cannot load texture: {:?}", path));
+        self.ramp = Some((0..image.width())
+            .map(|x| image.get_pixel(x, 0))
+            .map(|c| Vector3::new(f32::from(c.data[0]), f32::from(c.data[1]), f32::from(c.data[2])) / 255.0)
+            .collect());
+    }
+
+    /// Returns the color the lighting `intensity` is replaced with.
+    pub fn shade(&self, intensity: f32) -> Vector3 {
+        let intensity = intensity.max(0.0).min(1.0);
+
+        match self.ramp {
+            Some(ref ramp) => ramp[((intensity * ramp.len() as f32) as usize).min(ramp.len() - 1)],
+            None => {
+                let bands = self.bands.max(1) as f32;
+                let band = (intensity * bands).floor().min(bands - 1.0);
+                let level = (band + 1.0) / bands;
+                Vector3::new(level, level, level)
+            },
+        }
+    }
+
+    /// Draws silhouette and crease outlines over `framebuffer` where the depth, normals or object ids
+    /// recorded in `aovs` change between neighboring pixels.
+    pub fn draw_outlines(&self, framebuffer: &mut FrameBuffer, aovs: &AovBuffers) {
+        if self.outline_width == 0 {
+            return;
+        }
+
+        let (width, height) = (aovs.width(), aovs.height());
+        let crease_cos = self.crease_angle.to_radians().cos();
+        let radius = self.outline_width as isize;
+
+        let is_edge = |x: usize, y: usize, nx: usize, ny: usize| {
+            let (id, neighbor_id) = (aovs.object_id(x, y), aovs.object_id(nx, ny));
+            if id == 0 && neighbor_id == 0 {
+                return false;
+            }
+            if id != neighbor_id {
+                return true;
+            }
+
+            let (depth, neighbor_depth) = (aovs.depth(x, y), aovs.depth(nx, ny));
+            if (depth - neighbor_depth).abs() > DEPTH_THRESHOLD * depth.min(neighbor_depth) {
+                return true;
+            }
+
+            Vector3::dot(aovs.view_normal(x, y), aovs.view_normal(nx, ny)) < crease_cos
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                // Only the nearer side of an edge is outlined, so lines are `outline_width` pixels thick.
+                let outlined = (-radius..=radius)
+                    .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+                    .filter(|&(dx, dy)| (dx, dy) != (0, 0) && dx * dx + dy * dy <= radius * radius)
+                    .map(|(dx, dy)| (x as isize + dx, y as isize + dy))
+                    .filter(|&(nx, ny)| nx >= 0 && ny >= 0 && nx < width as isize && ny < height as isize)
+                    .any(|(nx, ny)| {
+                        let (nx, ny) = (nx as usize, ny as usize);
+                        aovs.depth(x, y) <= aovs.depth(nx, ny) && is_edge(x, y, nx, ny)
+                    });
+
+                if outlined {
+                    framebuffer.set(self.outline_color, x, y);
+                }
+            }
+        }
+    }
+}
+
+impl Default for Toon {
+    fn default() -> Self {
+        Toon {
+            bands: 3,
+            ramp: None,
+            outline_color: [0, 0, 0],
+            outline_width: 1,
+            crease_angle: 60.0,
+        }
+    }
+}
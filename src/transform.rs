@@ -0,0 +1,119 @@
+//! Transform Helpers
+
+extern crate tdmath;
+
+use self::tdmath::{Vector3, Vector4, Matrix4, Quaternion};
+
+/// Returns the identity rotation.
+pub fn quaternion_identity() -> Quaternion {
+    Quaternion { x: 0.0, y: 0.0, z: 0.0, w: 1.0 }
+}
+
+/// Returns a rotation of `angle` radians around `axis`.
+pub fn quaternion_from_axis_angle(axis: Vector3, angle: f32) -> Quaternion {
+    let axis = axis.normalized();
+    let half_sin = (angle * 0.5).sin();
+
+    Quaternion { x: axis.x * half_sin, y: axis.y * half_sin, z: axis.z * half_sin, w: (angle * 0.5).cos() }
+}
+
+/// Returns the rotation `a` applied after `b`.
+pub fn quaternion_multiply(a: Quaternion, b: Quaternion) -> Quaternion {
+    Quaternion {
+        x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+        y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+        z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+    }
+}
+
+/// Returns `q` scaled to unit length.
+pub fn quaternion_normalized(q: Quaternion) -> Quaternion {
+    let length = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+    if length == 0.0 {
+        return quaternion_identity();
+    }
+
+    Quaternion { x: q.x / length, y: q.y / length, z: q.z / length, w: q.w / length }
+}
+
+/// Returns `v` rotated by `q`.
+pub fn rotate_vector(q: Quaternion, v: Vector3) -> Vector3 {
+    let u = Vector3::new(q.x, q.y, q.z);
+    let t = Vector3::cross(u, v) * 2.0;
+
+    v + t * q.w + Vector3::cross(u, t)
+}
+
+/// Returns the rotation matrix of `q`.
+pub fn rotation_matrix(q: Quaternion) -> Matrix4 {
+    let q = quaternion_normalized(q);
+    let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+
+    Matrix4::new([
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0],
+        [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0],
+        [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ])
+}
+
+/// Transforms normals by the inverse-transpose of a matrix's upper 3x3,
+/// keeping them perpendicular to surfaces under rotation and non-uniform scale.
+#[derive(Debug, Copy, Clone)]
+pub struct NormalMatrix {
+    x: Vector3,
+    y: Vector3,
+    z: Vector3,
+}
+
+impl NormalMatrix {
+    /// Returns the normal matrix of `matrix`.
+    pub fn new(matrix: Matrix4) -> NormalMatrix {
+        let column = |x, y, z| (matrix * Vector4::new(x, y, z, 0.0)).xyz();
+        let (c0, c1, c2) = (column(1.0, 0.0, 0.0), column(0.0, 1.0, 0.0), column(0.0, 0.0, 1.0));
+
+        // The cofactor matrix is the inverse-transpose scaled by the determinant.
+        // Its columns are the cross products of the matrix columns.
+        let sign = if Vector3::dot(c0, Vector3::cross(c1, c2)) < 0.0 { -1.0 } else { 1.0 };
+
+        NormalMatrix {
+            x: Vector3::cross(c1, c2) * sign,
+            y: Vector3::cross(c2, c0) * sign,
+            z: Vector3::cross(c0, c1) * sign,
+        }
+    }
+
+    /// Returns `normal` transformed by this matrix and normalized.
+    pub fn transform(&self, normal: Vector3) -> Vector3 {
+        (self.x * normal.x + self.y * normal.y + self.z * normal.z).normalized()
+    }
+}
+
+/// Returns the spherical interpolation between `a` and `b` at `t`.
+pub fn quaternion_slerp(a: Quaternion, b: Quaternion, t: f32) -> Quaternion {
+    let mut cos_theta = a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w;
+
+    // Take the shortest path between the two rotations.
+    let b = if cos_theta < 0.0 {
+        cos_theta = -cos_theta;
+        Quaternion { x: -b.x, y: -b.y, z: -b.z, w: -b.w }
+    } else {
+        b
+    };
+
+    let (scale_a, scale_b) = if cos_theta > 0.9995 {
+        (1.0 - t, t)
+    } else {
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        (((1.0 - t) * theta).sin() / sin_theta, (t * theta).sin() / sin_theta)
+    };
+
+    quaternion_normalized(Quaternion {
+        x: a.x * scale_a + b.x * scale_b,
+        y: a.y * scale_a + b.y * scale_b,
+        z: a.z * scale_a + b.z * scale_b,
+        w: a.w * scale_a + b.w * scale_b,
+    })
+}
@@ -0,0 +1,89 @@
+//! Turntable Export
+
+extern crate gif;
+
+use std::f32::consts::PI;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use image;
+use self::gif::{Encoder, Frame, Repeat, SetParameter};
+use renderer::Renderer;
+use aov::{Aov, DepthFormat};
+
+/// Delay between GIF frames in hundredths of a second.
+const GIF_FRAME_DELAY: u16 = 4;
+
+/// Largest GIF frame width or height.
+const MAX_GIF_SIZE: usize = 65535;
+
+/// Renders `frame_count` frames of a full orbit around the camera target and writes them to `path`.
+/// A `.gif` path is written as a looping animated GIF, any other path as numbered PNGs.
+/// `elevation` is the camera's orbit pitch in degrees, the current pitch is kept if None.
+/// `aovs` are written next to each frame of a PNG sequence, they are skipped with a warning for a GIF.
+/// Returns an error if the frames are too large for a GIF.
+pub fn export(renderer: &mut Renderer, path: &Path, frame_count: u32, elevation: Option<f32>, aovs: &[Aov], depth_format: DepthFormat) -> Result<(), String> {
+    let frame_count = frame_count.max(1);
+
+    if let Some(elevation) = elevation {
+        renderer.set_elevation(elevation.to_radians());
+    }
+
+    let is_gif = path.extension().map_or(false, |e| e.eq_ignore_ascii_case("gif"));
+    if !aovs.is_empty() {
+        if is_gif {
+            eprintln!("AOVs are only written with PNG frames, skipping them for {:?}", path);
+        } else {
+            renderer.enable_aovs();
+        }
+    }
+
+    let (width, height) = (renderer.framebuffer().width(), renderer.framebuffer().height());
+    if is_gif && (width > MAX_GIF_SIZE || height > MAX_GIF_SIZE) {
+        return Err(format!("GIF frames cannot be larger than {} pixels: {}x{}", MAX_GIF_SIZE, width, height));
+    }
+
+    let mut gif_encoder = if is_gif {
+        let file = File::create(path).unwrap_or_else(|_| panic!("Unable to create file: {:?}", path));
+        let mut encoder = Encoder::new(file, width as u16, height as u16, &[])
+            .unwrap_or_else(|_| panic!("Unable to write GIF: {:?}", path));
+        encoder.set(Repeat::Infinite).unwrap();
+        Some(encoder)
+    } else {
+        None
+    };
+
+    let step = 2.0 * PI / frame_count as f32;
+
+    for frame_index in 0..frame_count {
+        renderer.render();
+        let framebuffer = renderer.framebuffer();
+
+        match gif_encoder {
+            Some(ref mut encoder) => {
+                let mut frame = Frame::from_rgb(width as u16, height as u16, framebuffer.pixels());
+                frame.delay = GIF_FRAME_DELAY;
+                encoder.write_frame(&frame).unwrap_or_else(|_| panic!("Unable to write GIF: {:?}", path));
+            },
+            None => {
+                let frame_path = frame_path(path, frame_index);
+                image::save_buffer(&frame_path, framebuffer.pixels(), framebuffer.width() as u32, framebuffer.height() as u32, image::RGB(8))
+                    .unwrap_or_else(|_| panic!("Unable to write image: {:?}", frame_path));
+
+                if let Some(buffers) = renderer.aovs() {
+                    let (near, far) = renderer.clip_planes();
+                    buffers.write(&frame_path, aovs, depth_format, near, far);
+                }
+            },
+        }
+
+        renderer.orbit(step, 0.0);
+    }
+
+    Ok(())
+}
+
+/// Returns the path of frame `frame_index` in a numbered PNG sequence, e.g. `turntable_0007.png`.
+fn frame_path(path: &Path, frame_index: u32) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("turntable");
+    path.with_file_name(format!("{}_{:04}.png", stem, frame_index))
+}
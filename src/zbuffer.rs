@@ -3,9 +3,12 @@
 use std::f32;
 
 /// ZBuffer
+/// Stores `sample_count` depth samples for every pixel.
 pub struct ZBuffer {
     buffer: Vec<f32>,
     width: usize,
+    height: usize,
+    sample_count: usize,
 }
 
 impl ZBuffer {
@@ -14,23 +17,38 @@ impl ZBuffer {
         ZBuffer {
             buffer: vec![0.0; (width * height) as usize],
             width,
+            height,
+            sample_count: 1,
         }
     }
 
     /// Resizes this `ZBuffer`.
     pub fn resize(&mut self, width: usize, height: usize) {
         self.width = width;
-        self.buffer = vec![0.0; (width * height) as usize];
+        self.height = height;
+        self.buffer = vec![0.0; (width * height * self.sample_count) as usize];
     }
 
-    /// Returns the value at `x`, `y`.
-    pub fn sample(&self, x: usize, y: usize) -> f32 {
-        self.buffer[x + self.width * y]
+    /// Sets the number of samples stored per pixel.
+    pub fn set_sample_count(&mut self, sample_count: usize) {
+        self.sample_count = sample_count;
+        let (width, height) = (self.width, self.height);
+        self.resize(width, height);
     }
 
-    /// Sets the value at `x`, `y`.
-    pub fn set(&mut self, value: f32, x: usize, y: usize) {
-        self.buffer[x + self.width * y] = value;
+    /// Returns the number of samples stored per pixel.
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    /// Returns the value of `sample` at `x`, `y`.
+    pub fn sample(&self, x: usize, y: usize, sample: usize) -> f32 {
+        self.buffer[(x + self.width * y) * self.sample_count + sample]
+    }
+
+    /// Sets the value of `sample` at `x`, `y`.
+    pub fn set(&mut self, value: f32, x: usize, y: usize, sample: usize) {
+        self.buffer[(x + self.width * y) * self.sample_count + sample] = value;
     }
 
     /// Sets all values to `f32::MAX`.
@@ -39,4 +57,4 @@ impl ZBuffer {
             self.buffer[i] = f32::MAX;
         }
     }
-}
\ No newline at end of file
+}